@@ -1,30 +1,112 @@
+use crate::utils::money::{FeeRounding, FeeSchedule};
 use std::env;
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+pub struct ProcessorConfig {
+    pub name: String,
+    pub url: String,
+    pub fee: FeeSchedule,
+    // Menor valor = tentado primeiro
+    pub priority: u32,
+    pub timeout_ms: u64,
+    pub circuit_breaker: BreakerSettings,
+}
+
+// Parâmetros do circuit breaker; cada processor pode sobrescrever os globais
+#[derive(Debug, Clone)]
+pub struct BreakerSettings {
+    pub failure_rate: f64,
+    pub window_size: usize,
+    pub window_secs: u64,
+    pub min_requests: usize,
+    pub half_open_probes: u32,
+    pub timeout_secs: u64,
+}
+
+impl Default for BreakerSettings {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.5,
+            window_size: 50,
+            window_secs: 10,
+            min_requests: 10,
+            half_open_probes: 3,
+            timeout_secs: 30,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub server_port: u16,
+    pub unix_socket_path: Option<String>,
+    pub unix_socket_mode: u32,
     pub token: String,
-    pub default_processor_url: String,
-    pub fallback_processor_url: String,
+    pub processors: Vec<ProcessorConfig>,
     pub batch_size: usize,
     pub queue_buffer_size: usize,
-    pub circuit_breaker_threshold: u32,
-    pub circuit_breaker_timeout_secs: u64,
+    pub worker_concurrency: usize,
+    pub admission_wait_ms: u64,
+    pub admission_retry_after_secs: u64,
+    pub circuit_breaker: BreakerSettings,
+    pub health_check_interval_ms: u64,
+    pub health_lease_path: Option<String>,
+    pub health_lease_ttl_ms: u64,
+    pub replica_id: String,
+    pub routing_max_latency_ms: u64,
+    pub routing_max_failure_rate: f64,
+    pub routing_delay_cost_bps_per_sec: u64,
+    pub routing_metrics_half_life_ms: u64,
+    pub concurrency_limit_initial: usize,
+    pub concurrency_limit_min: usize,
+    pub concurrency_limit_max: usize,
+    pub concurrency_latency_tolerance: f64,
+    pub concurrency_backoff_ratio: f64,
+    pub concurrency_acquire_timeout_ms: u64,
+    pub shutdown_drain_timeout_ms: u64,
+    pub reconcile_interval_secs: u64,
+    pub reconcile_max_id_checks: usize,
+    pub reconcile_settle_secs: u64,
+    pub peer_urls: Vec<String>,
+    pub peer_timeout_ms: u64,
+    pub summary_require_all_peers: bool,
+    pub store_backend: String,
+    pub store_path: String,
+    pub store_compact_bytes: u64,
+    pub journal_path: Option<String>,
+    pub journal_flush_interval_ms: u64,
+    pub retry_max_attempts: u32,
+    pub retry_max_age_ms: u64,
+    pub retry_base_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
 }
 
 impl Config {
-    pub fn from_env() -> Self {
-        Self {
+    pub fn from_env() -> Result<Self, String> {
+        let legacy = legacy_breaker_threshold(
+            env::var("CIRCUIT_BREAKER_THRESHOLD").ok().as_deref(),
+            env::var("CIRCUIT_BREAKER_MIN_REQUESTS").is_ok(),
+        );
+        let circuit_breaker = breaker_from_env("", &legacy);
+        let processors = processors_from_env(&circuit_breaker)?;
+
+        Ok(Self {
             server_port: env::var("PORT")
                 .unwrap_or_else(|_| "9999".to_string())
                 .parse()
                 .unwrap_or(9999),
+            unix_socket_path: env::var("UNIX_SOCKET_PATH")
+                .ok()
+                .filter(|path| !path.trim().is_empty()),
+            // Modo em octal, como no chmod
+            unix_socket_mode: env::var("UNIX_SOCKET_MODE")
+                .ok()
+                .and_then(|v| u32::from_str_radix(v.trim(), 8).ok())
+                .unwrap_or(0o660),
             token: env::var("TOKEN")
                 .unwrap_or_else(|_| "123".to_string()),
-            default_processor_url: env::var("DEFAULT_PROCESSOR_URL")
-                .unwrap_or_else(|_| "http://payment-processor-default:8080".to_string()),
-            fallback_processor_url: env::var("FALLBACK_PROCESSOR_URL")
-                .unwrap_or_else(|_| "http://payment-processor-fallback:8080".to_string()),
+            processors,
             batch_size: env::var("BATCH_SIZE")
                 .unwrap_or_else(|_| "50".to_string())
                 .parse()
@@ -33,14 +115,309 @@ impl Config {
                 .unwrap_or_else(|_| "1000".to_string())
                 .parse()
                 .unwrap_or(1000),
-            circuit_breaker_threshold: env::var("CIRCUIT_BREAKER_THRESHOLD")
+            worker_concurrency: env::var("WORKER_CONCURRENCY")
+                .unwrap_or_else(|_| "32".to_string())
+                .parse()
+                .unwrap_or(32),
+            admission_wait_ms: env::var("ADMISSION_WAIT_MS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            admission_retry_after_secs: env::var("ADMISSION_RETRY_AFTER_SECS")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .unwrap_or(1),
+            circuit_breaker,
+            health_check_interval_ms: env::var("HEALTH_CHECK_INTERVAL_MS")
+                .unwrap_or_else(|_| "5000".to_string())
+                .parse()
+                .unwrap_or(5000),
+            // Sem arquivo de lease cada réplica consulta o service-health sozinha
+            health_lease_path: env::var("HEALTH_LEASE_PATH")
+                .ok()
+                .filter(|path| !path.trim().is_empty()),
+            health_lease_ttl_ms: env::var("HEALTH_LEASE_TTL_MS")
+                .unwrap_or_else(|_| "15000".to_string())
+                .parse()
+                .unwrap_or(15000),
+            replica_id: env::var("REPLICA_ID")
+                .or_else(|_| env::var("HOSTNAME"))
+                .ok()
+                .filter(|id| !id.trim().is_empty())
+                .unwrap_or_else(|| format!("replica-{:016x}", fastrand::u64(..))),
+            routing_max_latency_ms: env::var("ROUTING_MAX_LATENCY_MS")
+                .unwrap_or_else(|_| "250".to_string())
+                .parse()
+                .unwrap_or(250),
+            routing_max_failure_rate: env::var("ROUTING_MAX_FAILURE_RATE")
+                .unwrap_or_else(|_| "0.2".to_string())
+                .parse()
+                .unwrap_or(0.2),
+            routing_delay_cost_bps_per_sec: env::var("ROUTING_DELAY_COST_BPS_PER_SEC")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .unwrap_or(500),
+            // Métricas de um processor sem tráfego perdem metade do peso a cada meia-vida
+            routing_metrics_half_life_ms: env::var("ROUTING_METRICS_HALF_LIFE_MS")
+                .unwrap_or_else(|_| "5000".to_string())
+                .parse()
+                .unwrap_or(5000),
+            // Limite adaptativo de chamadas simultâneas, por processor
+            concurrency_limit_initial: env::var("CONCURRENCY_LIMIT_INITIAL")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
+            concurrency_limit_min: env::var("CONCURRENCY_LIMIT_MIN")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .unwrap_or(2),
+            concurrency_limit_max: env::var("CONCURRENCY_LIMIT_MAX")
+                .unwrap_or_else(|_| "200".to_string())
+                .parse()
+                .unwrap_or(200),
+            concurrency_latency_tolerance: env::var("CONCURRENCY_LATENCY_TOLERANCE")
+                .unwrap_or_else(|_| "2.0".to_string())
+                .parse()
+                .unwrap_or(2.0),
+            concurrency_backoff_ratio: env::var("CONCURRENCY_BACKOFF_RATIO")
+                .unwrap_or_else(|_| "0.9".to_string())
+                .parse()
+                .unwrap_or(0.9),
+            concurrency_acquire_timeout_ms: env::var("CONCURRENCY_ACQUIRE_TIMEOUT_MS")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()
+                .unwrap_or(50),
+            // Vem depois da drenagem das conexões; os dois juntos precisam
+            // caber no stop_grace_period do docker-compose
+            shutdown_drain_timeout_ms: env::var("SHUTDOWN_DRAIN_TIMEOUT_MS")
+                .unwrap_or_else(|_| "8000".to_string())
+                .parse()
+                .unwrap_or(8000),
+            reconcile_interval_secs: env::var("RECONCILE_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            reconcile_max_id_checks: env::var("RECONCILE_MAX_ID_CHECKS")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .unwrap_or(500),
+            // Maior que o timeout dos processors: nada em andamento cai na janela
+            reconcile_settle_secs: env::var("RECONCILE_SETTLE_SECS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
+            peer_urls: env::var("PEER_URLS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|url| url.trim().trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty())
+                .collect(),
+            peer_timeout_ms: env::var("PEER_TIMEOUT_MS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .unwrap_or(1000),
+            // Com peer fora, 503 em vez dos totais parciais; "false" aceita
+            // o summary parcial marcado no header
+            summary_require_all_peers: env::var("SUMMARY_REQUIRE_ALL_PEERS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            store_backend: env::var("STORE_BACKEND")
+                .unwrap_or_else(|_| "memory".to_string()),
+            store_path: env::var("STORE_PATH")
+                .unwrap_or_else(|_| "payments.store".to_string()),
+            // Tamanho mínimo do log do store antes de compactar
+            store_compact_bytes: env::var("STORE_COMPACT_BYTES")
+                .unwrap_or_else(|_| "33554432".to_string())
+                .parse()
+                .unwrap_or(32 * 1024 * 1024),
+            journal_path: env::var("JOURNAL_PATH")
+                .ok()
+                .filter(|path| !path.trim().is_empty()),
+            journal_flush_interval_ms: env::var("JOURNAL_FLUSH_INTERVAL_MS")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .unwrap_or(2),
+            retry_max_attempts: env::var("RETRY_MAX_ATTEMPTS")
                 .unwrap_or_else(|_| "5".to_string())
                 .parse()
                 .unwrap_or(5),
-            circuit_breaker_timeout_secs: env::var("CIRCUIT_BREAKER_TIMEOUT")
-                .unwrap_or_else(|_| "30".to_string())
+            retry_max_age_ms: env::var("RETRY_MAX_AGE_MS")
+                .unwrap_or_else(|_| "60000".to_string())
+                .parse()
+                .unwrap_or(60000),
+            retry_base_delay_ms: env::var("RETRY_BASE_DELAY_MS")
+                .unwrap_or_else(|_| "200".to_string())
                 .parse()
-                .unwrap_or(30),
+                .unwrap_or(200),
+            retry_max_delay_ms: env::var("RETRY_MAX_DELAY_MS")
+                .unwrap_or_else(|_| "5000".to_string())
+                .parse()
+                .unwrap_or(5000),
+        })
+    }
+
+    pub fn processor(&self, name: &str) -> Option<&ProcessorConfig> {
+        self.processors.iter().find(|p| p.name == name)
+    }
+
+    pub fn fee_for(&self, processor: &str) -> Option<FeeSchedule> {
+        self.processor(processor).map(|p| p.fee)
+    }
+}
+
+// PROCESSORS=default,fallback,... e, por processor, {NAME}_PROCESSOR_URL,
+// {NAME}_PRIORITY, {NAME}_TIMEOUT_MS, as variáveis de taxa e as do circuit
+// breaker ({NAME}_CIRCUIT_BREAKER_*). Ordenado por prioridade.
+fn processors_from_env(breaker_defaults: &BreakerSettings) -> Result<Vec<ProcessorConfig>, String> {
+    let names = env::var("PROCESSORS")
+        .unwrap_or_else(|_| "default,fallback".to_string());
+    parse_processors(&names, breaker_defaults)
+}
+
+fn parse_processors(names: &str, breaker_defaults: &BreakerSettings) -> Result<Vec<ProcessorConfig>, String> {
+    let mut processors: Vec<ProcessorConfig> = Vec::new();
+    for name in names.split(',').map(str::trim) {
+        // "failed" e "pending" são estados de pagamento, não processors
+        if name.is_empty() || matches!(name, "failed" | "pending") {
+            warn!("Ignoring invalid processor name {:?} in PROCESSORS", name);
+            continue;
+        }
+
+        // Nomes que só diferem em caixa ou -/_ leriam as mesmas variáveis
+        let prefix = env_prefix(name);
+        if let Some(existing) = processors.iter().find(|p| env_prefix(&p.name) == prefix) {
+            return Err(format!("PROCESSORS lists {:?} more than once (as {:?})", name, existing.name));
+        }
+
+        let default_rate = match name {
+            "default" => 0.05,
+            "fallback" => 0.15,
+            _ => 0.0,
+        };
+        processors.push(ProcessorConfig {
+            name: name.to_string(),
+            url: env::var(format!("{}_PROCESSOR_URL", prefix))
+                .unwrap_or_else(|_| format!("http://payment-processor-{}:8080", name)),
+            fee: fee_from_env(&prefix, default_rate),
+            priority: env::var(format!("{}_PRIORITY", prefix))
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(processors.len() as u32),
+            timeout_ms: env::var(format!("{}_TIMEOUT_MS", prefix))
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5000),
+            circuit_breaker: breaker_from_env(&format!("{}_", prefix), breaker_defaults),
+        });
+    }
+
+    if processors.is_empty() {
+        return Err(format!("PROCESSORS has no valid processor: {:?}", names));
+    }
+
+    processors.sort_by_key(|p| p.priority);
+    Ok(processors)
+}
+
+// CIRCUIT_BREAKER_THRESHOLD era o número de falhas que abria o breaker antigo,
+// por contagem. Ainda é aceito como mínimo de chamadas na janela, com aviso;
+// CIRCUIT_BREAKER_MIN_REQUESTS tem precedência
+fn legacy_breaker_threshold(threshold: Option<&str>, min_requests_set: bool) -> BreakerSettings {
+    let defaults = BreakerSettings::default();
+    let Some(threshold) = threshold else {
+        return defaults;
+    };
+
+    if min_requests_set {
+        warn!("CIRCUIT_BREAKER_THRESHOLD is deprecated and ignored because CIRCUIT_BREAKER_MIN_REQUESTS is set");
+        return defaults;
+    }
+
+    match threshold.trim().parse::<usize>() {
+        Ok(min_requests) if min_requests > 0 => {
+            warn!(
+                "CIRCUIT_BREAKER_THRESHOLD is deprecated; using it as CIRCUIT_BREAKER_MIN_REQUESTS={} \
+                 (the breaker opens at CIRCUIT_BREAKER_FAILURE_RATE within that many calls)",
+                min_requests
+            );
+            BreakerSettings { min_requests, ..defaults }
         }
+        _ => {
+            warn!("Ignoring invalid deprecated CIRCUIT_BREAKER_THRESHOLD={:?}", threshold);
+            defaults
+        }
+    }
+}
+
+fn env_prefix(name: &str) -> String {
+    name.to_uppercase().replace('-', "_")
+}
+
+// {PREFIX}CIRCUIT_BREAKER_FAILURE_RATE, _WINDOW_SIZE, _WINDOW_SECS,
+// _MIN_REQUESTS, _HALF_OPEN_PROBES e _TIMEOUT (segundos); o que não estiver
+// definido vem de `defaults`
+fn breaker_from_env(prefix: &str, defaults: &BreakerSettings) -> BreakerSettings {
+    fn var<T: std::str::FromStr>(prefix: &str, key: &str, default: T) -> T {
+        env::var(format!("{}CIRCUIT_BREAKER_{}", prefix, key))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
     }
-}
\ No newline at end of file
+
+    BreakerSettings {
+        failure_rate: var(prefix, "FAILURE_RATE", defaults.failure_rate),
+        window_size: var(prefix, "WINDOW_SIZE", defaults.window_size),
+        window_secs: var(prefix, "WINDOW_SECS", defaults.window_secs),
+        min_requests: var(prefix, "MIN_REQUESTS", defaults.min_requests),
+        half_open_probes: var(prefix, "HALF_OPEN_PROBES", defaults.half_open_probes),
+        timeout_secs: var(prefix, "TIMEOUT", defaults.timeout_secs),
+    }
+}
+
+// {PREFIX}_FEE_RATE, {PREFIX}_FEE_FIXED_CENTS e FEE_ROUNDING (half_up | up | down)
+fn fee_from_env(prefix: &str, default_rate: f64) -> FeeSchedule {
+    let rate = env::var(format!("{}_FEE_RATE", prefix))
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default_rate);
+    let fixed_cents = env::var(format!("{}_FEE_FIXED_CENTS", prefix))
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let rounding = env::var("FEE_ROUNDING")
+        .ok()
+        .and_then(|v| FeeRounding::parse(&v))
+        .unwrap_or(FeeRounding::HalfUp);
+
+    FeeSchedule::new(rate, fixed_cents, rounding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_processors_rejects_empty_and_duplicates() {
+        let defaults = BreakerSettings::default();
+
+        let processors = parse_processors("fallback, failed, ,default", &defaults).unwrap();
+        let names: Vec<&str> = processors.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["fallback", "default"]);
+
+        assert!(parse_processors("", &defaults).is_err());
+        assert!(parse_processors("failed,pending", &defaults).is_err());
+        assert!(parse_processors("default,fallback,default", &defaults).is_err());
+        assert!(parse_processors("fast-lane,FAST_LANE", &defaults).is_err());
+    }
+
+    #[test]
+    fn test_legacy_breaker_threshold_maps_to_min_requests() {
+        let defaults = BreakerSettings::default();
+
+        assert_eq!(legacy_breaker_threshold(None, false).min_requests, defaults.min_requests);
+        assert_eq!(legacy_breaker_threshold(Some("5"), false).min_requests, 5);
+        assert_eq!(legacy_breaker_threshold(Some("5"), true).min_requests, defaults.min_requests);
+        assert_eq!(legacy_breaker_threshold(Some("abc"), false).min_requests, defaults.min_requests);
+    }
+}
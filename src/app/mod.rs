@@ -1 +1,3 @@
-pub mod config;
\ No newline at end of file
+pub mod config;
+#[cfg(unix)]
+pub mod unix_socket;
@@ -0,0 +1,154 @@
+use axum::Router;
+use hyper::server::conn::http1;
+use hyper_util::rt::TokioIo;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::UnixListener;
+use tracing::{error, info, warn};
+
+// Quanto esperar as conexões abertas terminarem depois do sinal de shutdown
+const CONNECTION_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+// Pausa após um erro de accept (ex.: EMFILE) para não girar o loop a 100% de CPU
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Listener HTTP em Unix domain socket; o arquivo é removido no drop.
+pub struct UnixSocketListener {
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl UnixSocketListener {
+    // Remove um socket órfão de uma execução anterior, mas nunca um que ainda
+    // tem alguém escutando nem um arquivo que não seja socket
+    pub fn bind(path: impl AsRef<Path>, mode: u32) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        remove_stale_socket(&path)?;
+
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+
+        Ok(Self { path, listener })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn serve(self, app: Router, shutdown: impl Future<Output = ()>) {
+        let graceful = GracefulShutdown::new();
+        tokio::pin!(shutdown);
+
+        loop {
+            let stream = tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("Failed to accept unix socket connection: {}", e);
+                        tokio::select! {
+                            _ = tokio::time::sleep(ACCEPT_ERROR_BACKOFF) => continue,
+                            _ = &mut shutdown => break,
+                        }
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+
+            let service = TowerToHyperService::new(app.clone());
+            let connection = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service);
+            let connection = graceful.watch(connection);
+
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    warn!("Unix socket connection error: {}", e);
+                }
+            });
+        }
+
+        if tokio::time::timeout(CONNECTION_DRAIN_TIMEOUT, graceful.shutdown()).await.is_err() {
+            warn!("Unix socket connections still open after {:?}", CONNECTION_DRAIN_TIMEOUT);
+        }
+    }
+}
+
+impl Drop for UnixSocketListener {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                error!("Failed to remove unix socket {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        ));
+    }
+
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{} is in use by another process", path.display()),
+        )),
+        // Só ConnectionRefused prova que ninguém escuta; outros erros (permissão,
+        // backlog cheio) não autorizam apagar o socket de outro processo
+        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+            info!("Removing stale unix socket {}", path.display());
+            fs::remove_file(path)
+        }
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!("cannot tell whether {} is stale: {}", path.display(), e),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_replaces_stale_socket_only() {
+        let dir = std::env::temp_dir().join(format!("unix-socket-test-{}", std::process::id()));
+        let path = dir.join("app.sock");
+        let _ = fs::remove_dir_all(&dir);
+
+        // Socket órfão: o processo que o criou já não escuta
+        fs::create_dir_all(&dir).unwrap();
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let listener = UnixSocketListener::bind(&path, 0o660).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o660);
+
+        // Com alguém escutando, não remove
+        assert!(UnixSocketListener::bind(&path, 0o660).is_err());
+
+        drop(listener);
+        assert!(!path.exists());
+
+        fs::write(&path, b"not a socket").unwrap();
+        assert!(UnixSocketListener::bind(&path, 0o660).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
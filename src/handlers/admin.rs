@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::handlers::payments_summary::parse_filters;
+use crate::services::reconciler::ReconciliationReport;
+use crate::services::PaymentService;
+
+pub const ADMIN_TOKEN_HEADER: &str = "X-Rinha-Token";
+
+fn authorize(service: &PaymentService, headers: &HeaderMap, action: &str) -> Result<(), StatusCode> {
+    let token = headers
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    if !service.is_admin_token_valid(token) {
+        warn!("Rejected {} request with invalid token", action);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(())
+}
+
+pub async fn purge_payments(
+    State(service): State<Arc<PaymentService>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    authorize(&service, &headers, "purge")?;
+
+    let result = service.purge().await;
+    info!("Payments purged");
+
+    Ok(Json(serde_json::json!({
+        "status": "purged",
+        "payments": result.payments,
+        "queued_discarded": result.queued_discarded
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct ReconciliationQuery {
+    #[serde(alias = "de")]
+    from: Option<String>,
+    #[serde(alias = "ate")]
+    to: Option<String>,
+    #[serde(default)]
+    ids: bool,
+}
+
+pub async fn reconcile_payments(
+    State(service): State<Arc<PaymentService>>,
+    headers: HeaderMap,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<Json<ReconciliationReport>, Response> {
+    authorize(&service, &headers, "reconciliation").map_err(IntoResponse::into_response)?;
+
+    let filters = parse_filters(query.from.as_deref(), query.to.as_deref())
+        .map_err(IntoResponse::into_response)?;
+
+    let report = service.reconcile(filters, query.ids).await;
+    info!("Reconciliation finished, consistent: {}", report.consistent);
+
+    Ok(Json(report))
+}
\ No newline at end of file
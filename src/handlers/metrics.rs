@@ -10,22 +10,17 @@ pub async fn get_metrics(
     let total_fees = payment_service.get_total_fees();
 
     // Get circuit breaker status
-    let default_breaker = payment_service
-        .get_circuit_breaker_status("default")
-        .await;
-
-    let fallback_breaker = payment_service
-        .get_circuit_breaker_status("fallback")
-        .await;
+    let mut circuit_breakers = serde_json::Map::new();
+    for processor in payment_service.processor_names() {
+        let status = payment_service.get_circuit_breaker_status(&processor).await;
+        circuit_breakers.insert(processor, status.into());
+    }
 
     Json(serde_json::json!({
         "total_payments": total_payments,
         "total_amount_cents": total_amount,
         "total_fees_cents": total_fees,
-        "circuit_breakers": {
-            "default": default_breaker,
-            "fallback": fallback_breaker
-        },
+        "circuit_breakers": circuit_breakers,
         "detailed_metrics": payment_service.get_metrics().await
     }))
 }
\ No newline at end of file
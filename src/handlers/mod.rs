@@ -1,3 +1,4 @@
 pub mod payments;
 pub mod payments_summary;
-pub mod metrics;
\ No newline at end of file
+pub mod metrics;
+pub mod admin;
\ No newline at end of file
@@ -1,44 +1,88 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
 use serde_json::Value;
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
 use crate::services::{PaymentService, ServiceError};
-use crate::models::payment::PaymentRequest;
+use crate::models::payment::{normalize_correlation_id, PaymentRequest};
+use crate::utils::money::format_currency;
 
 pub async fn create_payment(
     State(service): State<Arc<PaymentService>>,
     Json(payload): Json<Value>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<(StatusCode, Json<Value>), Response> {
     let request: PaymentRequest = match serde_json::from_value(payload) {
         Ok(req) => req,
         Err(e) => {
             error!("Invalid payment request: {}", e);
-            return Err(StatusCode::BAD_REQUEST);
+            return Err(StatusCode::BAD_REQUEST.into_response());
         }
     };
 
-    info!("Received payment request: {}", request.id);
+    info!("Received payment request: {} ({})", request.id, format_currency(request.amount));
 
     match service.submit_payment(request).await {
         Ok(_) => {
             info!("Payment submitted successfully");
-            Ok(Json(serde_json::json!({
+            Ok((StatusCode::OK, Json(serde_json::json!({
                 "status": "accepted",
                 "message": "Payment submitted for processing"
-            })))
+            }))))
+        }
+        Err(ServiceError::Duplicate(existing)) => {
+            info!("Duplicate payment request: {}", existing.id);
+            Ok((StatusCode::CONFLICT, Json(serde_json::json!({
+                "status": "duplicate",
+                "message": "Payment already submitted",
+                "correlationId": existing.id,
+                "state": existing.status(),
+                "processor": existing.processor
+            }))))
         }
         Err(ServiceError::QueueFull) => {
-            error!("Queue is full");
-            Err(StatusCode::SERVICE_UNAVAILABLE)
+            warn!("Queue is full, rejecting payment");
+            Err(service_unavailable(service.retry_after_secs(), "Payment queue is saturated"))
+        }
+        Err(ServiceError::QueueClosed) => {
+            error!("Queue is closed");
+            Err(service_unavailable(service.retry_after_secs(), "Payment queue is not accepting payments"))
         }
         Err(e) => {
             error!("Failed to submit payment: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
         }
     }
+}
+
+fn service_unavailable(retry_after_secs: u64, message: &str) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(serde_json::json!({
+            "status": "rejected",
+            "message": message
+        })),
+    )
+        .into_response()
+}
+
+pub async fn get_payment(
+    State(service): State<Arc<PaymentService>>,
+    Path(correlation_id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let id = normalize_correlation_id(&correlation_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let payment = service.get_payment(&id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut body = serde_json::to_value(&payment).map_err(|e| {
+        error!("Failed to serialize payment {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    body["status"] = Value::from(payment.status());
+
+    Ok(Json(body))
 }
\ No newline at end of file
@@ -1,39 +1,109 @@
 use axum::{
     extract::{Query, State},
-    response::Json,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
 };
 use serde::Deserialize;
 use std::sync::Arc;
-use tracing::info;
+use std::time::SystemTime;
+use tracing::{info, warn};
 
+use crate::models::payment::Payment;
 use crate::services::{PaymentService, SummaryFilters};
+use crate::utils::time::parse_timestamp;
 
 #[derive(Deserialize)]
 pub struct SummaryQuery {
-    #[serde(rename = "de")]
-    from_date: Option<String>,
-    #[serde(rename = "ate")]
-    to_date: Option<String>,
+    #[serde(alias = "de")]
+    from: Option<String>,
+    #[serde(alias = "ate")]
+    to: Option<String>,
 }
 
+// Marca a resposta quando alguma réplica não entrou nos totais
+pub const PARTIAL_SUMMARY_HEADER: &str = "x-summary-partial";
+
 pub async fn get_summary(
     State(service): State<Arc<PaymentService>>,
     Query(query): Query<SummaryQuery>,
-) -> Json<serde_json::Value> {
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
     info!("Getting payments summary");
 
+    let filters = parse_filters(query.from.as_deref(), query.to.as_deref())?;
+
+    // Inclui os pagamentos das outras réplicas atrás do load balancer. Peer
+    // fora (ex.: restart) dá 503: um total parcial parece certo para quem
+    // audita. Com SUMMARY_REQUIRE_ALL_PEERS=false devolve o que foi possível
+    // somar, marcado como parcial
+    let summary = service.get_cluster_summary(filters).await;
+    if !summary.is_partial() {
+        return Ok(Json(serde_json::json!(summary.totals)).into_response());
+    }
+
+    if service.summary_requires_all_peers() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "error": "summary incomplete: peer replicas unreachable",
+                "peers": summary.unreachable_peers
+            })),
+        ));
+    }
+
+    Ok((
+        [(PARTIAL_SUMMARY_HEADER, "true")],
+        Json(serde_json::json!(summary.totals)),
+    ).into_response())
+}
+
+// Só os pagamentos desta réplica; é o que os peers consultam para montar o summary
+pub async fn get_local_payments(
+    State(service): State<Arc<PaymentService>>,
+    Query(query): Query<SummaryQuery>,
+) -> Result<Json<Vec<Payment>>, (StatusCode, Json<serde_json::Value>)> {
+    let filters = parse_filters(query.from.as_deref(), query.to.as_deref())?;
+
+    Ok(Json(service.processed_payments(filters)))
+}
+
+// Janela from/to validada; usada também pela reconciliação do admin
+pub(crate) fn parse_filters(
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<SummaryFilters, (StatusCode, Json<serde_json::Value>)> {
     let filters = SummaryFilters {
-        from_date: query.from_date,
-        to_date: query.to_date,
+        from_date: parse_date_param("from", from)?,
+        to_date: parse_date_param("to", to)?,
     };
 
-    let summary = service.get_summary(filters).await;
+    if let (Some(from), Some(to)) = (filters.from_date, filters.to_date) {
+        if from > to {
+            return Err(bad_request("'from' must not be after 'to'".to_string()));
+        }
+    }
+
+    Ok(filters)
+}
+
+fn parse_date_param(
+    name: &str,
+    value: Option<&str>,
+) -> Result<Option<SystemTime>, (StatusCode, Json<serde_json::Value>)> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        None => Ok(None),
+        Some(raw) => parse_timestamp(raw).map(Some).ok_or_else(|| {
+            warn!("Invalid '{}' date in summary query: {}", name, raw);
+            bad_request(format!(
+                "invalid '{}' date '{}': expected ISO-8601, e.g. 2025-07-15T12:34:56.000Z",
+                name, raw
+            ))
+        }),
+    }
+}
 
-    Json(serde_json::json!({
-        "total_amount_cents": summary.total_amount_cents,
-        "total_fee_cents": summary.total_fee_cents,
-        "count": summary.count,
-        "count_processed": summary.count_processed,
-        "count_failed": summary.count_failed
-    }))
-}
\ No newline at end of file
+fn bad_request(message: String) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({ "error": message })),
+    )
+}
@@ -13,67 +13,193 @@ use axum::{
 };
 use handlers::*;
 use services::{PaymentService, PaymentProcessorClient};
+use services::health_monitor::SERVICE_HEALTH_MIN_INTERVAL;
+use services::payment_journal::PaymentJournal;
+use services::payment_store;
+use services::peer_summary::LOCAL_PAYMENTS_PATH;
 use std::sync::Arc;
 use tokio::net::TcpListener;
-use tracing::info;
-use dashmap::DashMap;
-use models::payment::PaymentRequest;
-use tokio::sync::mpsc;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
 
-    let config = Config::from_env();
+    let config = Config::from_env().expect("Invalid configuration");
     info!("Starting Rinha Backend 2025 server on port {}", config.server_port);
 
-    let storage = Arc::new(DashMap::new());
+    let storage = payment_store::open_store(&config).expect("Failed to open payment store");
     let processor_client = Arc::new(PaymentProcessorClient::new(&config));
-    let (payment_sender, payment_receiver) = mpsc::channel::<PaymentRequest>(config.queue_buffer_size);
+    let (payment_sender, payment_receiver) = queue::create_queue(config.queue_buffer_size);
+
+    let (journal, replayed) = match &config.journal_path {
+        Some(path) => {
+            let flush_interval = tokio::time::Duration::from_millis(config.journal_flush_interval_ms);
+            let (journal, replayed) = PaymentJournal::open(path, flush_interval)
+                .expect("Failed to open payment journal");
+            (Some(journal), replayed)
+        }
+        None => (None, Vec::new()),
+    };
 
     let payment_service = Arc::new(PaymentService::new(
+        &config,
         storage,
         processor_client.clone(),
         payment_sender,
+        journal,
     ));
 
-    // Health check task
+    // Health check task (service-health permite 1 chamada a cada 5s; com
+    // HEALTH_LEASE_PATH só uma réplica consulta)
     tokio::spawn({
         let processor_client = processor_client.clone();
+        let interval = tokio::time::Duration::from_millis(config.health_check_interval_ms)
+            .max(SERVICE_HEALTH_MIN_INTERVAL);
         async move {
             loop {
-                let default_health = processor_client.health_check("default").await;
-                let fallback_health = processor_client.health_check("fallback").await;
-                
-                info!("Processor health - Default: {}, Fallback: {}", 
-                      if default_health { "healthy" } else { "unhealthy" },
-                      if fallback_health { "healthy" } else { "unhealthy" });
-                
-                tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+                processor_client.refresh_health().await;
+
+                let statuses: Vec<String> = processor_client.processor_names()
+                    .into_iter()
+                    .map(|processor| {
+                        let healthy = processor_client.get_health(&processor).is_some_and(|h| !h.failing);
+                        format!("{}: {}", processor, if healthy { "healthy" } else { "unhealthy" })
+                    })
+                    .collect();
+
+                info!("Processor health - {}", statuses.join(", "));
+
+                tokio::time::sleep(interval).await;
             }
         }
     });
 
     // Payment processing task
-    tokio::spawn({
+    let worker = tokio::spawn({
         let payment_service = payment_service.clone();
         async move {
             payment_service.process_payments_async(payment_receiver).await;
         }
     });
 
+    // Reconciliação periódica com o /admin/payments-summary dos processors
+    if config.reconcile_interval_secs > 0 {
+        tokio::spawn({
+            let payment_service = payment_service.clone();
+            let interval = tokio::time::Duration::from_secs(config.reconcile_interval_secs);
+            async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+
+                    let report = payment_service.reconcile(payment_service.settled_filters(), false).await;
+                    for processor in report.processors.iter().filter(|p| !p.is_consistent()) {
+                        warn!("Reconciliation drift on {} processor: count {:+}, amount {:+} cents{}",
+                              processor.processor,
+                              processor.count_drift,
+                              processor.amount_drift_cents,
+                              processor.error.as_ref().map(|e| format!(" ({})", e)).unwrap_or_default());
+                    }
+                }
+            }
+        });
+    }
+
+    // Recupera do journal o que foi aceito antes de um crash/restart
+    payment_service.restore(replayed).await;
+
     let app = Router::new()
         .route("/health", get(health_handler))
         .route("/payments", post(payments::create_payment))
+        .route("/payments/:correlation_id", get(payments::get_payment))
         .route("/payments-summary", get(payments_summary::get_summary))
         .route("/metrics", get(metrics::get_metrics))
-        .with_state(payment_service);
+        .route("/purge-payments", post(admin::purge_payments))
+        .route("/admin/reconciliation", get(admin::reconcile_payments))
+        .with_state(payment_service.clone());
+
+    // Rotas entre réplicas só no listener TCP; o load balancer fala pelo unix socket
+    let tcp_app = app.clone().merge(
+        Router::new()
+            .route(LOCAL_PAYMENTS_PATH, get(payments_summary::get_local_payments))
+            .with_state(payment_service.clone()),
+    );
+
+    // Um único sinal de shutdown para todos os listeners
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    tokio::spawn({
+        let payment_service = payment_service.clone();
+        async move {
+            shutdown_signal().await;
+            payment_service.begin_shutdown();
+            let _ = shutdown_tx.send(true);
+        }
+    });
+
+    #[cfg(unix)]
+    let unix_server = {
+        let listener = config.unix_socket_path.as_ref().map(|path| {
+            app::unix_socket::UnixSocketListener::bind(path, config.unix_socket_mode)
+                .expect("Failed to bind unix socket")
+        });
+        let app = app.clone();
+        let shutdown_rx = shutdown_rx.clone();
+        async move {
+            if let Some(listener) = listener {
+                info!("Server listening on unix:{}", listener.path().display());
+                listener.serve(app, wait_for_shutdown(shutdown_rx)).await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let unix_server = async {};
 
     let addr = format!("0.0.0.0:{}", config.server_port);
     let listener = TcpListener::bind(&addr).await.unwrap();
     info!("Server listening on {}", addr);
 
-    axum::serve(listener, app).await.unwrap();
+    let tcp_server = axum::serve(listener, tcp_app)
+        .with_graceful_shutdown(wait_for_shutdown(shutdown_rx));
+
+    let (tcp_result, ()) = tokio::join!(tcp_server, unix_server);
+    tcp_result.unwrap();
+
+    // Dá ao worker até o deadline para drenar a fila e os pagamentos em andamento
+    let deadline = tokio::time::Duration::from_millis(config.shutdown_drain_timeout_ms);
+    if tokio::time::timeout(deadline, worker).await.is_err() {
+        warn!("Payment queue not drained within {:?}", deadline);
+    }
+
+    payment_service.complete_shutdown().await;
+    info!("Server stopped");
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+async fn wait_for_shutdown(mut shutdown_rx: tokio::sync::watch::Receiver<bool>) {
+    let _ = shutdown_rx.wait_for(|stopping| *stopping).await;
 }
 
 async fn health_handler() -> StatusCode {
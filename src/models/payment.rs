@@ -1,26 +1,41 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
 use std::time::SystemTime;
+use uuid::Uuid;
 
+use crate::utils::money::decimal_cents;
+use crate::utils::time::iso_timestamp_opt;
+
+// Contrato oficial: { "correlationId": "<uuid>", "amount": 19.90 }
+// Internamente o valor continua em centavos.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaymentRequest {
+    #[serde(rename = "correlationId", deserialize_with = "deserialize_correlation_id")]
     pub id: String,
+    #[serde(serialize_with = "decimal_cents::serialize", deserialize_with = "deserialize_amount")]
     pub amount: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Payment {
+    #[serde(rename = "correlationId")]
     pub id: String,
+    #[serde(with = "decimal_cents")]
     pub amount: u64,
     pub processor: String,
+    #[serde(with = "decimal_cents")]
     pub fee: u64,
+    #[serde(rename = "processedAt", with = "iso_timestamp_opt")]
     pub processed_at: Option<SystemTime>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProcessorResponse {
-    pub success: bool,
-    pub fee: Option<u64>,
-    pub error_message: Option<String>,
+impl Payment {
+    pub fn status(&self) -> &'static str {
+        match (self.processor.as_str(), self.processed_at) {
+            (_, None) => "pending",
+            ("failed", Some(_)) => "failed",
+            (_, Some(_)) => "processed",
+        }
+    }
 }
 
 // Payload para enviar aos Payment Processors
@@ -28,7 +43,27 @@ pub struct ProcessorResponse {
 pub struct ProcessorPayload {
     #[serde(rename = "correlationId")]
     pub correlation_id: String,
+    #[serde(with = "decimal_cents")]
     pub amount: u64,
     #[serde(rename = "requestedAt")]
-    pub requested_at: u64, // timestamp em milissegundos
-}
\ No newline at end of file
+    pub requested_at: String, // ISO-8601 em UTC, ex: 2025-07-15T12:34:56.000Z
+}
+
+fn deserialize_amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let cents = decimal_cents::deserialize(deserializer)?;
+    if cents == 0 {
+        return Err(D::Error::custom("amount must be greater than zero"));
+    }
+    Ok(cents)
+}
+
+fn deserialize_correlation_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    normalize_correlation_id(&raw)
+        .map_err(|e| D::Error::custom(format!("invalid correlationId '{}': {}", raw, e)))
+}
+
+// Forma canônica (hífens, minúsculas) usada como chave no storage
+pub fn normalize_correlation_id(raw: &str) -> Result<String, uuid::Error> {
+    Uuid::parse_str(raw).map(|uuid| uuid.to_string())
+}
@@ -1,3 +1,3 @@
 pub mod payment_queue;
 
-pub use payment_queue::create_queue;
\ No newline at end of file
+pub use payment_queue::{create_queue, QueuedPayment};
\ No newline at end of file
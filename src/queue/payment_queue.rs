@@ -1,6 +1,14 @@
 use crate::models::payment::PaymentRequest;
 use tokio::sync::mpsc::{self, Sender, Receiver};
 
-pub fn create_queue(buffer: usize) -> (Sender<PaymentRequest>, Receiver<PaymentRequest>) {
+// Item da fila: o pedido e a geração de purge em que foi admitido. Itens de
+// uma geração anterior ao último purge são descartados pelo worker
+#[derive(Debug, Clone)]
+pub struct QueuedPayment {
+    pub request: PaymentRequest,
+    pub generation: u64,
+}
+
+pub fn create_queue(buffer: usize) -> (Sender<QueuedPayment>, Receiver<QueuedPayment>) {
     mpsc::channel(buffer)
-}
\ No newline at end of file
+}
@@ -4,6 +4,10 @@ pub struct AtomicMetrics {
     submitted: AtomicU64,
     processed: AtomicU64,
     failed: AtomicU64,
+    duplicates: AtomicU64,
+    retries_scheduled: AtomicU64,
+    retries_exhausted: AtomicU64,
+    rejected: AtomicU64,
 }
 
 impl AtomicMetrics {
@@ -12,6 +16,10 @@ impl AtomicMetrics {
             submitted: AtomicU64::new(0),
             processed: AtomicU64::new(0),
             failed: AtomicU64::new(0),
+            duplicates: AtomicU64::new(0),
+            retries_scheduled: AtomicU64::new(0),
+            retries_exhausted: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
         }
     }
 
@@ -27,6 +35,32 @@ impl AtomicMetrics {
         self.failed.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn increment_duplicates(&self) {
+        self.duplicates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn increment_retries_scheduled(&self) {
+        self.retries_scheduled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn increment_retries_exhausted(&self) {
+        self.retries_exhausted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn increment_rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.submitted.store(0, Ordering::Relaxed);
+        self.processed.store(0, Ordering::Relaxed);
+        self.failed.store(0, Ordering::Relaxed);
+        self.duplicates.store(0, Ordering::Relaxed);
+        self.retries_scheduled.store(0, Ordering::Relaxed);
+        self.retries_exhausted.store(0, Ordering::Relaxed);
+        self.rejected.store(0, Ordering::Relaxed);
+    }
+
     pub fn get_submitted(&self) -> u64 {
         self.submitted.load(Ordering::Relaxed)
     }
@@ -38,4 +72,20 @@ impl AtomicMetrics {
     pub fn get_failed(&self) -> u64 {
         self.failed.load(Ordering::Relaxed)
     }
+
+    pub fn get_duplicates(&self) -> u64 {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+
+    pub fn get_retries_scheduled(&self) -> u64 {
+        self.retries_scheduled.load(Ordering::Relaxed)
+    }
+
+    pub fn get_retries_exhausted(&self) -> u64 {
+        self.retries_exhausted.load(Ordering::Relaxed)
+    }
+
+    pub fn get_rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,374 @@
+use crate::app::config::BreakerSettings;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CircuitBreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    pub failure_rate_threshold: f64,
+    // Janela por contagem (últimas N chamadas) e, opcionalmente, por tempo
+    pub window_size: usize,
+    pub window_duration: Option<Duration>,
+    pub min_requests: usize,
+    pub open_timeout: Duration,
+    pub half_open_probes: u32,
+}
+
+impl CircuitBreakerConfig {
+    pub fn from_settings(settings: &BreakerSettings) -> Self {
+        Self {
+            failure_rate_threshold: settings.failure_rate,
+            window_size: settings.window_size.max(1),
+            window_duration: match settings.window_secs {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            min_requests: settings.min_requests.max(1),
+            open_timeout: Duration::from_secs(settings.timeout_secs),
+            half_open_probes: settings.half_open_probes.max(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CircuitBreakerTransitions {
+    pub opened: u64,
+    pub half_opened: u64,
+    pub closed: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitBreakerSnapshot {
+    pub state: CircuitBreakerState,
+    pub failure_rate: f64,
+    pub window_requests: usize,
+    pub probes_in_flight: u32,
+    pub transitions: CircuitBreakerTransitions,
+    pub last_transition_ms_ago: Option<u64>,
+}
+
+/// Breaker compartilhado pelo client dos processors e pelo SmartFallbackManager.
+///
+/// `can_execute` admite a chamada (e pode mover Open -> HalfOpen); toda chamada
+/// admitida deve terminar em exatamente um `record_*`.
+pub trait CircuitBreaker: Send + Sync {
+    fn can_execute(&mut self) -> bool;
+    fn record_success(&mut self);
+    fn record_failure(&mut self);
+    /// Chamada admitida que terminou sem dizer nada sobre a saúde do processor
+    /// (ex.: 400); libera a vaga de sonda sem contar como sucesso ou falha
+    fn record_ignored(&mut self);
+    /// Estado efetivo: um Open com timeout vencido já conta como HalfOpen
+    fn state(&self) -> CircuitBreakerState;
+    /// Consulta sem efeito colateral, para ranking de processors
+    fn allows_requests(&self) -> bool;
+    fn reset(&mut self);
+    fn snapshot(&mut self) -> CircuitBreakerSnapshot;
+}
+
+// Implementação padrão: taxa de falha sobre janela deslizante
+pub struct SlidingWindowBreaker {
+    name: String,
+    config: CircuitBreakerConfig,
+    state: CircuitBreakerState,
+    // (instante, sucesso) das últimas chamadas, mais antiga na frente
+    outcomes: VecDeque<(Instant, bool)>,
+    opened_at: Option<Instant>,
+    probes_in_flight: u32,
+    probe_successes: u32,
+    transitions: CircuitBreakerTransitions,
+    last_transition: Option<Instant>,
+}
+
+impl SlidingWindowBreaker {
+    pub fn new(name: &str, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            outcomes: VecDeque::with_capacity(config.window_size),
+            config,
+            state: CircuitBreakerState::Closed,
+            opened_at: None,
+            probes_in_flight: 0,
+            probe_successes: 0,
+            transitions: CircuitBreakerTransitions::default(),
+            last_transition: None,
+        }
+    }
+
+    fn push_outcome(&mut self, success: bool) {
+        let now = Instant::now();
+        self.outcomes.push_back((now, success));
+        self.prune(now);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self.outcomes.len() > self.config.window_size {
+            self.outcomes.pop_front();
+        }
+        if let Some(window) = self.config.window_duration {
+            while let Some(&(at, _)) = self.outcomes.front() {
+                if now.duration_since(at) <= window {
+                    break;
+                }
+                self.outcomes.pop_front();
+            }
+        }
+    }
+
+    fn open_timeout_elapsed(&self) -> bool {
+        self.opened_at
+            .map(|opened| opened.elapsed() >= self.config.open_timeout)
+            .unwrap_or(true)
+    }
+
+    fn failure_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        let failures = self.outcomes.iter().filter(|(_, success)| !success).count();
+        failures as f64 / self.outcomes.len() as f64
+    }
+
+    fn should_trip(&self) -> bool {
+        self.outcomes.len() >= self.config.min_requests
+            && self.failure_rate() >= self.config.failure_rate_threshold
+    }
+
+    fn transition(&mut self, next: CircuitBreakerState) {
+        if self.state == next {
+            return;
+        }
+
+        match next {
+            CircuitBreakerState::Open => {
+                self.transitions.opened += 1;
+                self.opened_at = Some(Instant::now());
+                warn!(
+                    "Circuit breaker for {} processor opened (failure rate {:.2} over {} requests)",
+                    self.name, self.failure_rate(), self.outcomes.len()
+                );
+            }
+            CircuitBreakerState::HalfOpen => {
+                self.transitions.half_opened += 1;
+                info!("Circuit breaker for {} processor half-open, probing", self.name);
+            }
+            CircuitBreakerState::Closed => {
+                self.transitions.closed += 1;
+                info!("Circuit breaker for {} processor closed", self.name);
+            }
+        }
+
+        // Cada estado começa com a janela e as sondas zeradas
+        self.state = next;
+        self.outcomes.clear();
+        self.probes_in_flight = 0;
+        self.probe_successes = 0;
+        self.last_transition = Some(Instant::now());
+    }
+}
+
+impl CircuitBreaker for SlidingWindowBreaker {
+    fn can_execute(&mut self) -> bool {
+        match self.state {
+            CircuitBreakerState::Closed => true,
+            CircuitBreakerState::Open => {
+                if !self.open_timeout_elapsed() {
+                    return false;
+                }
+                self.transition(CircuitBreakerState::HalfOpen);
+                self.probes_in_flight = 1;
+                true
+            }
+            // Só deixa passar as sondas que ainda faltam para decidir
+            CircuitBreakerState::HalfOpen => {
+                let admitted = self.allows_requests();
+                if admitted {
+                    self.probes_in_flight += 1;
+                }
+                admitted
+            }
+        }
+    }
+
+    fn record_success(&mut self) {
+        match self.state {
+            CircuitBreakerState::HalfOpen => {
+                self.probes_in_flight = self.probes_in_flight.saturating_sub(1);
+                self.probe_successes += 1;
+                if self.probe_successes >= self.config.half_open_probes {
+                    self.transition(CircuitBreakerState::Closed);
+                }
+            }
+            CircuitBreakerState::Closed => self.push_outcome(true),
+            CircuitBreakerState::Open => {}
+        }
+    }
+
+    fn record_failure(&mut self) {
+        match self.state {
+            // Qualquer sonda falhando reabre o circuito
+            CircuitBreakerState::HalfOpen => self.transition(CircuitBreakerState::Open),
+            CircuitBreakerState::Open => {}
+            CircuitBreakerState::Closed => {
+                self.push_outcome(false);
+                if self.should_trip() {
+                    self.transition(CircuitBreakerState::Open);
+                }
+            }
+        }
+    }
+
+    fn record_ignored(&mut self) {
+        if self.state == CircuitBreakerState::HalfOpen {
+            self.probes_in_flight = self.probes_in_flight.saturating_sub(1);
+        }
+    }
+
+    fn state(&self) -> CircuitBreakerState {
+        if self.state == CircuitBreakerState::Open && self.open_timeout_elapsed() {
+            CircuitBreakerState::HalfOpen
+        } else {
+            self.state
+        }
+    }
+
+    fn allows_requests(&self) -> bool {
+        match self.state {
+            CircuitBreakerState::Closed => true,
+            CircuitBreakerState::Open => self.open_timeout_elapsed(),
+            CircuitBreakerState::HalfOpen => {
+                self.probes_in_flight + self.probe_successes < self.config.half_open_probes
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = CircuitBreakerState::Closed;
+        self.outcomes.clear();
+        self.opened_at = None;
+        self.probes_in_flight = 0;
+        self.probe_successes = 0;
+        self.transitions = CircuitBreakerTransitions::default();
+        self.last_transition = None;
+    }
+
+    fn snapshot(&mut self) -> CircuitBreakerSnapshot {
+        self.prune(Instant::now());
+        CircuitBreakerSnapshot {
+            state: self.state(),
+            failure_rate: self.failure_rate(),
+            window_requests: self.outcomes.len(),
+            probes_in_flight: self.probes_in_flight,
+            transitions: self.transitions.clone(),
+            last_transition_ms_ago: self.last_transition
+                .map(|at| at.elapsed().as_millis() as u64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_breaker(half_open_probes: u32) -> SlidingWindowBreaker {
+        SlidingWindowBreaker::new("test", CircuitBreakerConfig {
+            failure_rate_threshold: 0.5,
+            window_size: 10,
+            window_duration: None,
+            min_requests: 4,
+            open_timeout: Duration::ZERO,
+            half_open_probes,
+        })
+    }
+
+    #[test]
+    fn test_breaker_trips_on_failure_rate() {
+        let mut breaker = test_breaker(2);
+
+        // Abaixo do volume mínimo não abre, mesmo com 100% de falha
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state, CircuitBreakerState::Closed);
+
+        // 4 falhas em 9 chamadas ainda fica abaixo de 50%
+        for _ in 0..5 {
+            breaker.record_success();
+        }
+        breaker.record_failure();
+        assert_eq!(breaker.state, CircuitBreakerState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state, CircuitBreakerState::Open);
+        assert_eq!(breaker.snapshot().transitions.opened, 1);
+    }
+
+    #[test]
+    fn test_breaker_half_open_limits_probes() {
+        let mut breaker = test_breaker(2);
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state, CircuitBreakerState::Open);
+
+        // Timeout zero: a próxima chamada vira sonda
+        assert!(breaker.can_execute());
+        assert_eq!(breaker.state, CircuitBreakerState::HalfOpen);
+        assert!(breaker.can_execute());
+        assert!(!breaker.can_execute());
+
+        // Sonda sem veredito devolve a vaga
+        breaker.record_ignored();
+        assert!(breaker.can_execute());
+
+        breaker.record_success();
+        assert_eq!(breaker.state, CircuitBreakerState::HalfOpen);
+        breaker.record_success();
+        assert_eq!(breaker.state, CircuitBreakerState::Closed);
+
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.transitions.half_opened, 1);
+        assert_eq!(snapshot.transitions.closed, 1);
+        assert_eq!(snapshot.window_requests, 0);
+    }
+
+    #[test]
+    fn test_breaker_probe_failure_reopens() {
+        let mut breaker = test_breaker(1);
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+        assert!(breaker.can_execute());
+        breaker.record_failure();
+        assert_eq!(breaker.state, CircuitBreakerState::Open);
+        assert_eq!(breaker.snapshot().transitions.opened, 2);
+    }
+
+    #[test]
+    fn test_breaker_reports_half_open_without_new_results() {
+        let mut breaker = test_breaker(1);
+        for _ in 0..4 {
+            breaker.record_failure();
+        }
+
+        // Timeout vencido já aparece como HalfOpen antes de qualquer chamada
+        assert_eq!(breaker.state(), CircuitBreakerState::HalfOpen);
+        assert!(breaker.allows_requests());
+        assert_eq!(breaker.state, CircuitBreakerState::Open);
+
+        assert!(breaker.can_execute());
+        assert!(!breaker.allows_requests());
+
+        breaker.reset();
+        assert_eq!(breaker.state(), CircuitBreakerState::Closed);
+        assert_eq!(breaker.snapshot().transitions.opened, 0);
+    }
+}
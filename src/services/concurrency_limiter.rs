@@ -0,0 +1,292 @@
+use crate::app::config::Config;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+// Peso de cada amostra acima do baseline: o baseline sobe devagar quando a
+// latência do processor muda de patamar, mas cai na hora
+const BASELINE_DRIFT: f64 = 0.01;
+
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitConfig {
+    pub initial_limit: usize,
+    pub min_limit: usize,
+    pub max_limit: usize,
+    // Latência acima de baseline * tolerance conta como sobrecarga
+    pub latency_tolerance: f64,
+    pub backoff_ratio: f64,
+}
+
+impl ConcurrencyLimitConfig {
+    pub fn from_config(config: &Config) -> Self {
+        let min_limit = config.concurrency_limit_min.max(1);
+        let max_limit = config.concurrency_limit_max.max(min_limit);
+        Self {
+            initial_limit: config.concurrency_limit_initial.clamp(min_limit, max_limit),
+            min_limit,
+            max_limit,
+            latency_tolerance: config.concurrency_latency_tolerance.max(1.0),
+            backoff_ratio: config.concurrency_backoff_ratio.clamp(0.1, 0.99),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimiterOutcome {
+    Success,
+    // Erro que indica sobrecarga (timeout, 5xx, conexão)
+    Failure,
+    // Não diz nada sobre a carga do processor (ex.: payload rejeitado)
+    Ignored,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LimiterSnapshot {
+    pub limit: usize,
+    pub in_flight: usize,
+    pub baseline_latency_ms: Option<u64>,
+    pub increases: u64,
+    pub decreases: u64,
+}
+
+struct LimiterState {
+    limit: f64,
+    in_flight: usize,
+    baseline: Option<Duration>,
+    last_decrease: Option<Instant>,
+    increases: u64,
+    decreases: u64,
+}
+
+impl LimiterState {
+    fn current_limit(&self) -> usize {
+        self.limit as usize
+    }
+}
+
+/// Limite adaptativo (AIMD) de chamadas simultâneas a um processor: cresce
+/// enquanto a latência fica perto do baseline e corta quando ela sobe ou
+/// aparecem erros.
+pub struct AdaptiveLimiter {
+    config: ConcurrencyLimitConfig,
+    state: Mutex<LimiterState>,
+    released: Notify,
+}
+
+impl AdaptiveLimiter {
+    pub fn new(config: ConcurrencyLimitConfig) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                limit: config.initial_limit as f64,
+                in_flight: 0,
+                baseline: None,
+                last_decrease: None,
+                increases: 0,
+                decreases: 0,
+            }),
+            config,
+            released: Notify::new(),
+        }
+    }
+
+    // Espera uma vaga; o permit libera a vaga no drop
+    pub async fn acquire(&self) -> LimiterPermit<'_> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < state.current_limit() {
+                    state.in_flight += 1;
+                    // O limite pode ter crescido: acorda o próximo da fila
+                    if state.in_flight < state.current_limit() {
+                        self.released.notify_one();
+                    }
+                    return LimiterPermit { limiter: self, started: Instant::now(), done: false };
+                }
+            }
+            self.released.notified().await;
+        }
+    }
+
+    // Vaga sem esperar; None com o limite cheio
+    pub fn try_acquire(&self) -> Option<LimiterPermit<'_>> {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight >= state.current_limit() {
+            return None;
+        }
+        state.in_flight += 1;
+        Some(LimiterPermit { limiter: self, started: Instant::now(), done: false })
+    }
+
+    // Espera uma vaga por no máximo `wait`
+    pub async fn acquire_timeout(&self, wait: Duration) -> Option<LimiterPermit<'_>> {
+        if let Some(permit) = self.try_acquire() {
+            return Some(permit);
+        }
+        tokio::time::timeout(wait, self.acquire()).await.ok()
+    }
+
+    pub fn snapshot(&self) -> LimiterSnapshot {
+        let state = self.state.lock().unwrap();
+        LimiterSnapshot {
+            limit: state.current_limit(),
+            in_flight: state.in_flight,
+            baseline_latency_ms: state.baseline.map(|b| b.as_millis() as u64),
+            increases: state.increases,
+            decreases: state.decreases,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().current_limit()
+    }
+
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = self.config.initial_limit as f64;
+        state.baseline = None;
+        state.last_decrease = None;
+        state.increases = 0;
+        state.decreases = 0;
+    }
+
+    fn complete(&self, started: Instant, outcome: LimiterOutcome) {
+        let latency = started.elapsed();
+        let mut state = self.state.lock().unwrap();
+        // Só cresce se o limite estava sendo usado; ocioso não prova nada
+        let utilized = state.in_flight * 2 >= state.current_limit();
+        state.in_flight -= 1;
+
+        let overloaded = match outcome {
+            LimiterOutcome::Ignored => None,
+            LimiterOutcome::Failure => Some(true),
+            LimiterOutcome::Success => {
+                let baseline = match state.baseline {
+                    Some(baseline) if latency >= baseline => {
+                        baseline + (latency - baseline).mul_f64(BASELINE_DRIFT)
+                    }
+                    _ => latency,
+                };
+                state.baseline = Some(baseline);
+                Some(latency > baseline.mul_f64(self.config.latency_tolerance))
+            }
+        };
+
+        match overloaded {
+            // Um corte por "rodada": chamadas que já estavam em voo no último
+            // corte não cortam de novo
+            Some(true) if state.last_decrease.is_none_or(|at| started > at) => {
+                state.limit = (state.limit * self.config.backoff_ratio).max(self.config.min_limit as f64);
+                state.last_decrease = Some(Instant::now());
+                state.decreases += 1;
+            }
+            Some(false) if utilized => {
+                let limit = (state.limit + 1.0 / state.limit).min(self.config.max_limit as f64);
+                if limit as usize > state.current_limit() {
+                    state.increases += 1;
+                }
+                state.limit = limit;
+            }
+            _ => {}
+        }
+
+        drop(state);
+        self.released.notify_one();
+    }
+}
+
+pub struct LimiterPermit<'a> {
+    limiter: &'a AdaptiveLimiter,
+    started: Instant,
+    done: bool,
+}
+
+impl LimiterPermit<'_> {
+    pub fn record(mut self, outcome: LimiterOutcome) {
+        self.done = true;
+        self.limiter.complete(self.started, outcome);
+    }
+}
+
+impl Drop for LimiterPermit<'_> {
+    // Chamada cancelada no meio: devolve a vaga sem mexer no limite
+    fn drop(&mut self) {
+        if !self.done {
+            self.limiter.complete(self.started, LimiterOutcome::Ignored);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ConcurrencyLimitConfig {
+        ConcurrencyLimitConfig {
+            initial_limit: 2,
+            min_limit: 1,
+            max_limit: 4,
+            latency_tolerance: 2.0,
+            backoff_ratio: 0.5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_at_limit() {
+        let limiter = AdaptiveLimiter::new(config());
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        assert_eq!(limiter.snapshot().in_flight, 2);
+
+        let blocked = tokio::time::timeout(Duration::from_millis(20), limiter.acquire()).await;
+        assert!(blocked.is_err());
+
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.acquire_timeout(Duration::from_millis(5)).await.is_none());
+
+        drop(first);
+        let third = limiter.acquire_timeout(Duration::from_millis(20)).await;
+        assert!(third.is_some());
+        assert_eq!(limiter.snapshot().in_flight, 2);
+    }
+
+    // Completa uma chamada com o limite todo ocupado, começada `latency_ms` atrás
+    fn complete_saturated(limiter: &AdaptiveLimiter, latency_ms: u64, outcome: LimiterOutcome) {
+        let started = Instant::now() - Duration::from_millis(latency_ms);
+        limiter.state.lock().unwrap().in_flight = limiter.limit();
+        limiter.complete(started, outcome);
+        limiter.state.lock().unwrap().in_flight = 0;
+    }
+
+    #[test]
+    fn test_grows_while_flat_and_cuts_on_overload() {
+        let limiter = AdaptiveLimiter::new(config());
+
+        for _ in 0..20 {
+            complete_saturated(&limiter, 10, LimiterOutcome::Success);
+        }
+        assert_eq!(limiter.limit(), 4);
+
+        complete_saturated(&limiter, 10, LimiterOutcome::Failure);
+        assert_eq!(limiter.limit(), 2);
+
+        // Chamada que já estava em voo no corte anterior não corta de novo
+        let in_flight_before = Instant::now() - Duration::from_millis(100);
+        limiter.state.lock().unwrap().in_flight = 1;
+        limiter.complete(in_flight_before, LimiterOutcome::Failure);
+        assert_eq!(limiter.limit(), 2);
+
+        // Latência bem acima do baseline também corta, mas nunca abaixo do mínimo
+        std::thread::sleep(Duration::from_millis(2));
+        let started = Instant::now();
+        std::thread::sleep(Duration::from_millis(30));
+        limiter.state.lock().unwrap().in_flight = 1;
+        limiter.complete(started, LimiterOutcome::Success);
+        assert_eq!(limiter.limit(), 1);
+
+        complete_saturated(&limiter, 10, LimiterOutcome::Ignored);
+        let snapshot = limiter.snapshot();
+        assert_eq!(snapshot.decreases, 2);
+        assert_eq!(snapshot.limit, 1);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::app::config::{Config, ProcessorConfig};
+use crate::services::health_monitor::{HealthMonitor, ProcessorHealth, SERVICE_HEALTH_MIN_INTERVAL};
+use crate::services::real_time_metrics::{MetricsCollector, ProcessorMetrics};
+use crate::utils::money::FeeSchedule;
+use crate::utils::time::format_timestamp;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+// Taxa de sucesso mínima usada na estimativa de espera, para não dividir por zero
+const MIN_SUCCESS_RATE: f64 = 0.05;
+// Service-health mais velho que isso não diz mais nada sobre o processor
+const MAX_HEALTH_AGE: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RoutingSlo {
+    pub max_latency_ms: u64,
+    pub max_failure_rate: f64,
+    // Quanto custa, em bps do valor do pagamento, cada segundo de espera
+    pub delay_cost_bps_per_sec: u64,
+    #[serde(skip)]
+    pub metrics_half_life: Duration,
+}
+
+impl RoutingSlo {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_latency_ms: config.routing_max_latency_ms,
+            max_failure_rate: config.routing_max_failure_rate,
+            delay_cost_bps_per_sec: config.routing_delay_cost_bps_per_sec,
+            metrics_half_life: Duration::from_millis(config.routing_metrics_half_life_ms.max(1)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingReason {
+    PrimaryWithinSlo,
+    PrimaryCheaperDespiteSlo,
+    CheaperThanWaiting,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingDecision {
+    pub processor: String,
+    pub reason: RoutingReason,
+    pub amount_cents: u64,
+    // Processor de maior prioridade entre os disponíveis
+    pub primary: String,
+    pub primary_latency_ms: u64,
+    pub primary_failure_rate: f64,
+    // Custo esperado = taxa + espera estimada precificada, em centavos
+    pub costs_cents: BTreeMap<String, f64>,
+    pub decided_at: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Observed {
+    latency: Duration,
+    failure_rate: f64,
+}
+
+impl Observed {
+    // As métricas só andam para o processor que recebe tráfego: sem amostras
+    // novas elas perdem peso para o service-health, e assim um primary que se
+    // recuperou volta a ser escolhido. Sem nenhum dado, o processor ganha o
+    // benefício da dúvida.
+    fn from_sources(metrics: Option<&ProcessorMetrics>, health: Option<&ProcessorHealth>, half_life: Duration) -> Self {
+        let health = health.filter(|h| h.age() <= MAX_HEALTH_AGE);
+        let prior = match health {
+            Some(h) => Self {
+                latency: Duration::from_millis(h.min_response_time_ms),
+                failure_rate: if h.failing { 1.0 } else { 0.0 },
+            },
+            None => Self { latency: Duration::ZERO, failure_rate: 0.0 },
+        };
+
+        let mut observed = match metrics {
+            Some(m) => {
+                let weight = 0.5f64.powf(m.last_update.elapsed().as_secs_f64() / half_life.as_secs_f64());
+                let failure_rate = (1.0 - m.success_rate).clamp(0.0, 1.0);
+                Self {
+                    latency: m.avg_latency.mul_f64(weight) + prior.latency.mul_f64(1.0 - weight),
+                    failure_rate: failure_rate * weight + prior.failure_rate * (1.0 - weight),
+                }
+            }
+            None => prior,
+        };
+
+        // minResponseTime é um piso para a latência e failing vale na hora: só
+        // saberemos que voltou no próximo poll do service-health
+        if let Some(h) = health {
+            observed.latency = observed.latency.max(prior.latency);
+            if h.failing {
+                observed.failure_rate = 1.0;
+                observed.latency = observed.latency.max(SERVICE_HEALTH_MIN_INTERVAL);
+            }
+        }
+        observed
+    }
+
+    // Tempo esperado até um sucesso, repetindo no mesmo processor
+    fn expected_wait_secs(&self) -> f64 {
+        let success_rate = (1.0 - self.failure_rate).max(MIN_SUCCESS_RATE);
+        self.latency.as_secs_f64() / success_rate
+    }
+}
+
+/// Decide entre os processors disponíveis pelo lucro líquido esperado.
+///
+/// Fica no de maior prioridade enquanto latência e taxa de falha estão dentro
+/// do SLO; fora dele, só troca se esperar por ele custar mais do que a
+/// diferença de taxa.
+pub struct FeeAwareRouter {
+    slo: RoutingSlo,
+    metrics: MetricsCollector,
+    last_decision: Mutex<Option<RoutingDecision>>,
+    routed: Mutex<HashMap<String, u64>>,
+}
+
+impl FeeAwareRouter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            slo: RoutingSlo::from_config(config),
+            metrics: MetricsCollector::new(),
+            last_decision: Mutex::new(None),
+            routed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn record(&self, processor: &str, latency: Duration, success: bool, fee: u64) {
+        self.metrics.record_request(latency, success, processor, fee).await;
+    }
+
+    // candidates em ordem de prioridade e não vazio
+    pub async fn decide(&self, amount: u64, candidates: &[&ProcessorConfig], health: &HealthMonitor) -> RoutingDecision {
+        let mut observed = Vec::with_capacity(candidates.len());
+        for processor in candidates {
+            let metrics = self.metrics.get_processor_metrics(&processor.name).await;
+            let health = health.get(&processor.name);
+            let current = Observed::from_sources(metrics.as_ref(), health.as_ref(), self.slo.metrics_half_life);
+            observed.push((processor.name.as_str(), processor.fee, current));
+        }
+
+        let decision = self.evaluate(amount, &observed);
+
+        *self.routed.lock().unwrap().entry(decision.processor.clone()).or_insert(0) += 1;
+        *self.last_decision.lock().unwrap() = Some(decision.clone());
+        decision
+    }
+
+    fn evaluate(&self, amount: u64, candidates: &[(&str, FeeSchedule, Observed)]) -> RoutingDecision {
+        let costs: Vec<f64> = candidates.iter()
+            .map(|(_, fee, observed)| self.expected_cost(amount, fee, *observed))
+            .collect();
+
+        let (primary, _, primary_observed) = candidates[0];
+        let within_slo = primary_observed.latency.as_millis() as u64 <= self.slo.max_latency_ms
+            && primary_observed.failure_rate <= self.slo.max_failure_rate;
+
+        // Empate fica com o de maior prioridade
+        let cheapest = (1..costs.len()).fold(0, |best, i| if costs[i] < costs[best] { i } else { best });
+
+        let (processor, reason) = if within_slo {
+            (primary, RoutingReason::PrimaryWithinSlo)
+        } else if cheapest == 0 {
+            (primary, RoutingReason::PrimaryCheaperDespiteSlo)
+        } else {
+            (candidates[cheapest].0, RoutingReason::CheaperThanWaiting)
+        };
+
+        RoutingDecision {
+            processor: processor.to_string(),
+            reason,
+            amount_cents: amount,
+            primary: primary.to_string(),
+            primary_latency_ms: primary_observed.latency.as_millis() as u64,
+            primary_failure_rate: primary_observed.failure_rate,
+            costs_cents: candidates.iter()
+                .zip(&costs)
+                .map(|((name, _, _), cost)| (name.to_string(), *cost))
+                .collect(),
+            decided_at: format_timestamp(SystemTime::now()),
+        }
+    }
+
+    fn expected_cost(&self, amount: u64, fee: &FeeSchedule, observed: Observed) -> f64 {
+        let delay_cost = amount as f64
+            * self.slo.delay_cost_bps_per_sec as f64
+            * observed.expected_wait_secs()
+            / 10_000.0;
+        fee.fee_for(amount) as f64 + delay_cost
+    }
+
+    pub async fn status(&self, processors: &[String]) -> serde_json::Value {
+        let mut observed = serde_json::Map::new();
+        for processor in processors {
+            if let Some(m) = self.metrics.get_processor_metrics(processor).await {
+                observed.insert(processor.clone(), serde_json::json!({
+                    "success_rate": m.success_rate,
+                    "avg_latency_ms": m.avg_latency.as_millis() as u64,
+                    "avg_fee_cents": m.fee_efficiency
+                }));
+            }
+        }
+
+        let routed: BTreeMap<String, u64> = self.routed.lock().unwrap()
+            .iter()
+            .map(|(name, count)| (name.clone(), *count))
+            .collect();
+
+        serde_json::json!({
+            "slo": self.slo,
+            "decision": self.last_decision.lock().unwrap().clone(),
+            "routed": routed,
+            "observed": observed
+        })
+    }
+
+    pub fn reset(&self) {
+        *self.last_decision.lock().unwrap() = None;
+        self.routed.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::config::BreakerSettings;
+    use crate::utils::money::FeeRounding;
+    use std::time::Instant;
+
+    fn router() -> FeeAwareRouter {
+        FeeAwareRouter {
+            slo: RoutingSlo {
+                max_latency_ms: 200,
+                max_failure_rate: 0.2,
+                delay_cost_bps_per_sec: 500,
+                metrics_half_life: Duration::from_millis(20),
+            },
+            metrics: MetricsCollector::new(),
+            last_decision: Mutex::new(None),
+            routed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn candidates(default_ms: u64, default_failure_rate: f64) -> Vec<(&'static str, FeeSchedule, Observed)> {
+        vec![
+            ("default", FeeSchedule::new(0.05, 0, FeeRounding::HalfUp), observed(default_ms, default_failure_rate)),
+            ("fallback", FeeSchedule::new(0.15, 0, FeeRounding::HalfUp), observed(10, 0.0)),
+        ]
+    }
+
+    fn observed(latency_ms: u64, failure_rate: f64) -> Observed {
+        Observed { latency: Duration::from_millis(latency_ms), failure_rate }
+    }
+
+    #[test]
+    fn test_routing_prefers_primary_within_slo() {
+        let decision = router().evaluate(10_000, &candidates(150, 0.1));
+        assert_eq!(decision.processor, "default");
+        assert_eq!(decision.reason, RoutingReason::PrimaryWithinSlo);
+    }
+
+    #[test]
+    fn test_routing_switches_only_when_waiting_costs_more() {
+        let router = router();
+
+        // Fora do SLO, mas 1s de espera (5%) ainda custa menos que os 10% de diferença
+        let decision = router.evaluate(10_000, &candidates(1_000, 0.0));
+        assert_eq!(decision.processor, "default");
+        assert_eq!(decision.reason, RoutingReason::PrimaryCheaperDespiteSlo);
+
+        // 3s de espera (15%) já passa da diferença de taxa
+        let decision = router.evaluate(10_000, &candidates(3_000, 0.0));
+        assert_eq!(decision.processor, "fallback");
+        assert_eq!(decision.reason, RoutingReason::CheaperThanWaiting);
+        assert_eq!(decision.costs_cents["default"], 500.0 + 1_500.0);
+    }
+
+    #[tokio::test]
+    async fn test_routing_returns_to_primary_after_recovery() {
+        let router = router();
+        let health = HealthMonitor::new(reqwest::Client::new());
+        let default = ProcessorConfig {
+            name: "default".to_string(),
+            url: String::new(),
+            fee: FeeSchedule::new(0.05, 0, FeeRounding::HalfUp),
+            priority: 0,
+            timeout_ms: 5_000,
+            circuit_breaker: BreakerSettings::default(),
+        };
+        let fallback = ProcessorConfig {
+            name: "fallback".to_string(),
+            fee: FeeSchedule::new(0.15, 0, FeeRounding::HalfUp),
+            priority: 1,
+            ..default.clone()
+        };
+
+        for _ in 0..5 {
+            router.record("default", Duration::from_millis(3_000), true, 500).await;
+            router.record("fallback", Duration::from_millis(10), true, 1_500).await;
+        }
+        let decision = router.decide(10_000, &[&default, &fallback], &health).await;
+        assert_eq!(decision.processor, "fallback");
+
+        // default não recebe mais tráfego; o service-health diz que voltou ao normal
+        health.apply("default", ProcessorHealth { failing: false, min_response_time_ms: 5, checked_at: Instant::now() });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let decision = router.decide(10_000, &[&default, &fallback], &health).await;
+        assert_eq!(decision.processor, "default");
+        assert_eq!(decision.reason, RoutingReason::PrimaryWithinSlo);
+
+        // Enquanto o service-health disser failing, nada de voltar
+        health.apply("default", ProcessorHealth { failing: true, min_response_time_ms: 5, checked_at: Instant::now() });
+        let decision = router.decide(10_000, &[&default, &fallback], &health).await;
+        assert_eq!(decision.processor, "fallback");
+    }
+}
@@ -0,0 +1,231 @@
+use crate::app::config::Config;
+use crate::services::health_monitor::ProcessorHealth;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tracing::info;
+
+// Conteúdo do arquivo compartilhado: quem detém o lease e o último health publicado
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LeaseState {
+    holder: String,
+    expires_at_ms: u64,
+    #[serde(default)]
+    health: BTreeMap<String, PublishedHealth>,
+}
+
+impl LeaseState {
+    fn held_by_other(&self, holder: &str, now_ms: u64) -> bool {
+        self.holder != holder && self.expires_at_ms > now_ms
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PublishedHealth {
+    failing: bool,
+    min_response_time_ms: u64,
+    checked_at_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum LeaseRole {
+    Leader,
+    Follower { leader: String },
+}
+
+pub enum LeaseOutcome {
+    Leader,
+    Follower(Vec<(String, ProcessorHealth)>),
+}
+
+/// Lease sobre um arquivo compartilhado entre as réplicas: só quem o detém
+/// consulta o service-health dos processors e publica o resultado no mesmo
+/// arquivo. Se o líder morre o lease expira e a próxima réplica assume.
+pub struct HealthLease {
+    path: PathBuf,
+    holder: String,
+    ttl: Duration,
+    role: Mutex<Option<LeaseRole>>,
+}
+
+impl HealthLease {
+    pub fn new(path: impl AsRef<Path>, holder: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            holder: holder.into(),
+            ttl,
+            role: Mutex::new(None),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.health_lease_path.as_ref().map(|path| {
+            Self::new(path, &config.replica_id, Duration::from_millis(config.health_lease_ttl_ms))
+        })
+    }
+
+    pub fn role(&self) -> Option<LeaseRole> {
+        self.role.lock().unwrap().clone()
+    }
+
+    // Renova o lease se estiver livre, expirado ou já for nosso; senão devolve o
+    // health publicado pelo líder. Duas réplicas podem assumir juntas um lease
+    // expirado: a última a gravar fica com ele e a outra desiste no publish.
+    pub async fn acquire(&self) -> io::Result<LeaseOutcome> {
+        let now_ms = now_ms();
+        let current = self.read().await?;
+
+        if let Some(state) = current.as_ref().filter(|s| s.held_by_other(&self.holder, now_ms)) {
+            self.set_role(LeaseRole::Follower { leader: state.holder.clone() });
+            return Ok(LeaseOutcome::Follower(to_health(&state.health, now_ms)));
+        }
+
+        // Mantém o último health publicado até o novo líder publicar o seu
+        let health = current.map(|state| state.health).unwrap_or_default();
+        self.write(&LeaseState {
+            holder: self.holder.clone(),
+            expires_at_ms: now_ms + self.ttl.as_millis() as u64,
+            health,
+        }).await?;
+
+        match self.read().await? {
+            Some(state) if state.holder != self.holder => {
+                self.set_role(LeaseRole::Follower { leader: state.holder.clone() });
+                Ok(LeaseOutcome::Follower(to_health(&state.health, now_ms)))
+            }
+            _ => {
+                self.set_role(LeaseRole::Leader);
+                Ok(LeaseOutcome::Leader)
+            }
+        }
+    }
+
+    // Publica o health e renova o lease; false se outra réplica assumiu nesse meio tempo
+    pub async fn publish(&self, health: &[(String, ProcessorHealth)]) -> io::Result<bool> {
+        let now_ms = now_ms();
+        if let Some(state) = self.read().await?.filter(|s| s.held_by_other(&self.holder, now_ms)) {
+            self.set_role(LeaseRole::Follower { leader: state.holder });
+            return Ok(false);
+        }
+
+        let health = health.iter()
+            .map(|(processor, health)| (processor.clone(), PublishedHealth {
+                failing: health.failing,
+                min_response_time_ms: health.min_response_time_ms,
+                checked_at_ms: now_ms.saturating_sub(health.age().as_millis() as u64),
+            }))
+            .collect();
+
+        self.write(&LeaseState {
+            holder: self.holder.clone(),
+            expires_at_ms: now_ms + self.ttl.as_millis() as u64,
+            health,
+        }).await?;
+        Ok(true)
+    }
+
+    // No shutdown expira o lease na hora para a outra réplica assumir sem esperar o TTL
+    pub async fn release(&self) -> io::Result<()> {
+        let Some(mut state) = self.read().await?.filter(|s| s.holder == self.holder) else {
+            return Ok(());
+        };
+
+        state.expires_at_ms = 0;
+        self.write(&state).await?;
+        info!("Released processor health lease {}", self.path.display());
+        Ok(())
+    }
+
+    fn set_role(&self, role: LeaseRole) {
+        let mut current = self.role.lock().unwrap();
+        if current.as_ref() != Some(&role) {
+            match &role {
+                LeaseRole::Leader => info!("Polling processor health as lease holder {}", self.holder),
+                LeaseRole::Follower { leader } => info!("Using processor health published by {}", leader),
+            }
+            *current = Some(role);
+        }
+    }
+
+    // Arquivo ausente ou ilegível (escrita interrompida) conta como lease livre.
+    // tokio::fs roda o I/O no pool de blocking, fora das threads do runtime
+    async fn read(&self) -> io::Result<Option<LeaseState>> {
+        match fs::read(&self.path).await {
+            Ok(raw) => Ok(serde_json::from_slice(&raw).ok()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Grava num temporário próprio da réplica e renomeia: quem lê nunca vê meio arquivo
+    async fn write(&self, state: &LeaseState) -> io::Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let tmp_path = self.path.with_extension(format!("{}.tmp", self.holder));
+        fs::write(&tmp_path, serde_json::to_vec(state)?).await?;
+        fs::rename(&tmp_path, &self.path).await
+    }
+}
+
+fn to_health(published: &BTreeMap<String, PublishedHealth>, now_ms: u64) -> Vec<(String, ProcessorHealth)> {
+    published.iter()
+        .map(|(processor, health)| {
+            let age = Duration::from_millis(now_ms.saturating_sub(health.checked_at_ms));
+            (processor.clone(), ProcessorHealth {
+                failing: health.failing,
+                min_response_time_ms: health.min_response_time_ms,
+                checked_at: Instant::now().checked_sub(age).unwrap_or_else(Instant::now),
+            })
+        })
+        .collect()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_single_leader_and_takeover() {
+        let path = std::env::temp_dir().join(format!("health-lease-test-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let app1 = HealthLease::new(&path, "app1", Duration::from_secs(30));
+        let app2 = HealthLease::new(&path, "app2", Duration::from_secs(30));
+
+        assert!(matches!(app1.acquire().await.unwrap(), LeaseOutcome::Leader));
+        let health = ProcessorHealth { failing: true, min_response_time_ms: 120, checked_at: Instant::now() };
+        assert!(app1.publish(&[("default".to_string(), health)]).await.unwrap());
+
+        match app2.acquire().await.unwrap() {
+            LeaseOutcome::Follower(health) => {
+                assert_eq!(health.len(), 1);
+                assert_eq!(health[0].0, "default");
+                assert!(health[0].1.failing);
+                assert_eq!(health[0].1.min_response_time_ms, 120);
+            }
+            LeaseOutcome::Leader => panic!("app2 should follow app1"),
+        }
+        assert_eq!(app2.role(), Some(LeaseRole::Follower { leader: "app1".to_string() }));
+
+        // Líder saiu: app2 assume e app1 não sobrescreve o que ele publicar
+        app1.release().await.unwrap();
+        assert!(matches!(app2.acquire().await.unwrap(), LeaseOutcome::Leader));
+        assert!(!app1.publish(&[]).await.unwrap());
+        assert_eq!(app1.role(), Some(LeaseRole::Follower { leader: "app2".to_string() }));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,191 @@
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// Os processors aceitam uma chamada a cada 5s por cliente
+pub const SERVICE_HEALTH_MIN_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessorHealth {
+    pub failing: bool,
+    #[serde(rename = "minResponseTime")]
+    pub min_response_time_ms: u64,
+    #[serde(skip)]
+    pub checked_at: Instant,
+}
+
+impl ProcessorHealth {
+    pub fn age(&self) -> Duration {
+        self.checked_at.elapsed()
+    }
+}
+
+// Payload de GET /payments/service-health
+#[derive(Debug, Deserialize)]
+struct ServiceHealthPayload {
+    failing: bool,
+    #[serde(rename = "minResponseTime")]
+    min_response_time: u64,
+}
+
+enum PollOutcome {
+    Healthy(ServiceHealthPayload),
+    RateLimited(Option<Duration>),
+    Unreachable,
+    Ignored,
+}
+
+#[derive(Default)]
+struct HealthEntry {
+    health: Option<ProcessorHealth>,
+    next_poll_at: Option<Instant>,
+    backoff: Duration,
+}
+
+pub struct HealthMonitor {
+    client: Client,
+    entries: Mutex<HashMap<String, HealthEntry>>,
+}
+
+impl HealthMonitor {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Consulta o endpoint respeitando o rate limit; fora da janela devolve o cache
+    pub async fn poll(&self, processor: &str, base_url: &str) -> Option<ProcessorHealth> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries.entry(processor.to_string()).or_default();
+            let now = Instant::now();
+
+            if entry.next_poll_at.is_some_and(|next| now < next) {
+                return entry.health.clone();
+            }
+
+            // Reserva a janela antes da chamada para evitar polls concorrentes
+            entry.next_poll_at = Some(now + SERVICE_HEALTH_MIN_INTERVAL.max(entry.backoff));
+        }
+
+        let outcome = match self.client
+            .get(format!("{}/payments/service-health", base_url))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+        {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = response.headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                PollOutcome::RateLimited(retry_after)
+            }
+            Ok(response) if response.status().is_success() => {
+                match response.json::<ServiceHealthPayload>().await {
+                    Ok(payload) => PollOutcome::Healthy(payload),
+                    Err(e) => {
+                        warn!("{} service-health returned invalid payload: {}", processor, e);
+                        PollOutcome::Ignored
+                    }
+                }
+            }
+            Ok(response) => {
+                warn!("{} service-health returned status: {}", processor, response.status());
+                PollOutcome::Ignored
+            }
+            Err(e) => {
+                warn!("{} service-health check failed: {}", processor, e);
+                PollOutcome::Unreachable
+            }
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(processor.to_string()).or_default();
+
+        match outcome {
+            PollOutcome::RateLimited(retry_after) => {
+                entry.backoff = next_backoff(entry.backoff, retry_after);
+                entry.next_poll_at = Some(Instant::now() + entry.backoff);
+                warn!("{} service-health rate limited, backing off for {:?}", processor, entry.backoff);
+            }
+            PollOutcome::Healthy(payload) => {
+                entry.backoff = Duration::ZERO;
+                entry.health = Some(ProcessorHealth {
+                    failing: payload.failing,
+                    min_response_time_ms: payload.min_response_time,
+                    checked_at: Instant::now(),
+                });
+                info!("{} processor health: failing={}, minResponseTime={}ms",
+                      processor, payload.failing, payload.min_response_time);
+            }
+            PollOutcome::Unreachable => {
+                // Processor inacessível conta como falhando
+                let min_response_time_ms = entry.health.as_ref().map_or(0, |h| h.min_response_time_ms);
+                entry.health = Some(ProcessorHealth {
+                    failing: true,
+                    min_response_time_ms,
+                    checked_at: Instant::now(),
+                });
+            }
+            PollOutcome::Ignored => {}
+        }
+
+        entry.health.clone()
+    }
+
+    pub fn get(&self, processor: &str) -> Option<ProcessorHealth> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(processor).and_then(|entry| entry.health.clone())
+    }
+
+    // Health publicado por outra réplica; só substitui se for mais recente
+    pub fn apply(&self, processor: &str, health: ProcessorHealth) {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(processor.to_string()).or_default();
+        if entry.health.as_ref().is_none_or(|current| current.checked_at < health.checked_at) {
+            entry.health = Some(health);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<(String, ProcessorHealth)> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter()
+            .filter_map(|(processor, entry)| entry.health.clone().map(|health| (processor.clone(), health)))
+            .collect()
+    }
+
+    pub fn is_failing(&self, processor: &str) -> bool {
+        self.get(processor).is_some_and(|health| health.failing)
+    }
+}
+
+fn next_backoff(current: Duration, retry_after: Option<Duration>) -> Duration {
+    let doubled = if current.is_zero() {
+        SERVICE_HEALTH_MIN_INTERVAL
+    } else {
+        current * 2
+    };
+
+    doubled.max(retry_after.unwrap_or(Duration::ZERO)).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_backoff() {
+        assert_eq!(next_backoff(Duration::ZERO, None), Duration::from_secs(5));
+        assert_eq!(next_backoff(Duration::from_secs(5), None), Duration::from_secs(10));
+        assert_eq!(next_backoff(Duration::from_secs(5), Some(Duration::from_secs(30))), Duration::from_secs(30));
+        assert_eq!(next_backoff(Duration::from_secs(40), None), MAX_BACKOFF);
+    }
+}
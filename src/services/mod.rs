@@ -1,18 +1,21 @@
 pub mod payment_service;
 pub mod payment_processor_client;
 pub mod atomic_metrics;
-pub mod batch_processor;
-pub mod http_client_pool;
 pub mod optimized_batch_processor;
-pub mod adaptive_monitor;
-pub mod intelligent_load_balancer;
-pub mod multi_cache;
-pub mod optimized_payments;
-pub mod payments;
-pub mod predictive_cache;
-pub mod processor_monitor;
 pub mod real_time_metrics;
 pub mod smart_fallback;
+pub mod health_monitor;
+pub mod health_lease;
+pub mod retry_scheduler;
+pub mod payment_journal;
+pub mod record_log;
+pub mod reconciler;
+pub mod circuit_breaker;
+pub mod concurrency_limiter;
+pub mod fee_router;
+pub mod processor_registry;
+pub mod peer_summary;
+pub mod payment_store;
 
 pub use payment_service::{PaymentService, ServiceError, SummaryFilters};
 pub use payment_processor_client::PaymentProcessorClient;
\ No newline at end of file
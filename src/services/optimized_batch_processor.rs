@@ -1,24 +1,26 @@
+use crate::app::config::Config;
 use crate::models::payment::{PaymentRequest, Payment};
 use crate::services::payment_processor_client::PaymentProcessorClient;
-use crate::services::http_client_pool::HttpClientPool;
+use futures::StreamExt;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::time::{Duration, interval};
 use tracing::{info, error};
 
+// Pipeline em lote alternativo ao worker do PaymentService; ainda não é usado pelo main
+#[allow(dead_code)]
 pub struct OptimizedBatchProcessor {
     processor_client: Arc<PaymentProcessorClient>,
-    http_pool: Arc<HttpClientPool>,
     batch_size: usize,
     flush_interval: Duration,
 }
 
+#[allow(dead_code)]
 impl OptimizedBatchProcessor {
-    pub fn new(processor_client: Arc<PaymentProcessorClient>) -> Self {
+    pub fn new(processor_client: Arc<PaymentProcessorClient>, config: &Config) -> Self {
         Self {
             processor_client,
-            http_pool: Arc::new(HttpClientPool::new(10)), // Pool de 10 clientes
-            batch_size: 50,
+            batch_size: config.batch_size.max(1),
             flush_interval: Duration::from_millis(100),
         }
     }
@@ -29,10 +31,12 @@ impl OptimizedBatchProcessor {
     ) -> mpsc::Receiver<Payment> {
         let (sender, processed_receiver) = mpsc::channel::<Payment>(2000);
         let processor_client = Arc::clone(&self.processor_client);
+        let batch_size = self.batch_size;
+        let flush_interval = self.flush_interval;
 
         tokio::spawn(async move {
-            let mut batch = Vec::with_capacity(50);
-            let mut flush_timer = interval(Duration::from_millis(100));
+            let mut batch = Vec::with_capacity(batch_size);
+            let mut flush_timer = interval(flush_interval);
 
             loop {
                 tokio::select! {
@@ -43,7 +47,7 @@ impl OptimizedBatchProcessor {
                                 batch.push(payment);
                                 
                                 // Processa batch quando cheio
-                                if batch.len() >= 50 {
+                                if batch.len() >= batch_size {
                                     Self::process_batch(
                                         &processor_client, 
                                         &sender, 
@@ -80,22 +84,15 @@ impl OptimizedBatchProcessor {
         let batch_size = batch.len();
         info!("Processing batch of {} payments", batch_size);
 
-        // Processa em paralelo usando futures
-        let mut handles = Vec::with_capacity(batch_size);
-        
-        for payment_req in batch.drain(..) {
-            let client = Arc::clone(processor_client); // Agora processor_client é &Arc<...>
-            let handle = tokio::spawn(async move {
-                client.process_payment(payment_req).await
-            });
-            handles.push(handle);
-        }
+        // Processa em paralelo, mas só até o limite adaptativo somado dos
+        // processors; o resto espera vaga em vez de virar task
+        let concurrency = processor_client.total_concurrency_limit().max(1);
+        let mut results = futures::stream::iter(batch.drain(..))
+            .map(|payment_req| processor_client.process_payment(payment_req))
+            .buffer_unordered(concurrency);
 
-        // Aguarda todos em paralelo
-        let results = futures::future::join_all(handles).await;
-        
-        for result in results {
-            if let Ok(Some(payment)) = result {
+        while let Some(result) = results.next().await {
+            if let Ok(payment) = result {
                 if sender.send(payment).await.is_err() {
                     error!("Failed to send processed payment");
                     break;
@@ -0,0 +1,229 @@
+use crate::models::payment::{Payment, PaymentRequest};
+use crate::services::record_log::{self, write_record};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info};
+
+// Uma linha JSON por evento; o arquivo é compactado a cada abertura
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEntry {
+    Admitted { request: PaymentRequest },
+    Released { id: String },
+    Completed { payment: Payment },
+    Purged,
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    Unavailable,
+}
+
+enum JournalCommand {
+    Append(JournalEntry, Option<oneshot::Sender<()>>),
+    Flush(oneshot::Sender<()>),
+}
+
+pub struct PaymentJournal {
+    path: PathBuf,
+    sender: mpsc::UnboundedSender<JournalCommand>,
+}
+
+impl PaymentJournal {
+    // Lê o journal existente, reescreve só o estado atual e abre para append.
+    // Devolve os pagamentos reconstruídos (pendentes têm processed_at = None).
+    pub fn open(path: impl AsRef<Path>, flush_interval: Duration) -> io::Result<(Self, Vec<Payment>)> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let payments = replay(&path)?;
+        compact(&path, &payments)?;
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        std::thread::Builder::new()
+            .name("payment-journal".to_string())
+            .spawn(move || run_writer(file, receiver, flush_interval))?;
+
+        info!("Payment journal opened at {} ({} payments replayed)", path.display(), payments.len());
+
+        Ok((Self { path, sender }, payments))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Retorna só depois do fsync do lote que contém a entrada
+    pub async fn append_durable(&self, entry: JournalEntry) -> Result<(), JournalError> {
+        let (ack, done) = oneshot::channel();
+        self.sender
+            .send(JournalCommand::Append(entry, Some(ack)))
+            .map_err(|_| JournalError::Unavailable)?;
+        done.await.map_err(|_| JournalError::Unavailable)
+    }
+
+    pub fn append(&self, entry: JournalEntry) {
+        if self.sender.send(JournalCommand::Append(entry, None)).is_err() {
+            error!("Payment journal writer is gone, entry dropped");
+        }
+    }
+
+    pub async fn flush(&self) -> Result<(), JournalError> {
+        let (ack, done) = oneshot::channel();
+        self.sender
+            .send(JournalCommand::Flush(ack))
+            .map_err(|_| JournalError::Unavailable)?;
+        done.await.map_err(|_| JournalError::Unavailable)
+    }
+}
+
+fn replay(path: &Path) -> io::Result<Vec<Payment>> {
+    let mut payments: HashMap<String, Payment> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+
+    record_log::read_records(path, "journal", |entry| match entry {
+        JournalEntry::Admitted { request } => {
+            if !payments.contains_key(&request.id) {
+                order.push(request.id.clone());
+                payments.insert(request.id.clone(), Payment {
+                    id: request.id,
+                    amount: request.amount,
+                    processor: "pending".to_string(),
+                    fee: 0,
+                    processed_at: None,
+                });
+            }
+        }
+        JournalEntry::Released { id } => {
+            if payments.get(&id).is_some_and(|p| p.processed_at.is_none()) {
+                payments.remove(&id);
+            }
+        }
+        JournalEntry::Completed { payment } => {
+            if !payments.contains_key(&payment.id) {
+                order.push(payment.id.clone());
+            }
+            payments.insert(payment.id.clone(), payment);
+        }
+        JournalEntry::Purged => {
+            payments.clear();
+            order.clear();
+        }
+    })?;
+
+    Ok(order.into_iter().filter_map(|id| payments.remove(&id)).collect())
+}
+
+fn compact(path: &Path, payments: &[Payment]) -> io::Result<()> {
+    let entries = payments.iter().map(|payment| {
+        if payment.processed_at.is_some() {
+            JournalEntry::Completed { payment: payment.clone() }
+        } else {
+            JournalEntry::Admitted {
+                request: PaymentRequest { id: payment.id.clone(), amount: payment.amount },
+            }
+        }
+    });
+    record_log::rewrite(path, entries).map(|_| ())
+}
+
+// Group commit: junta o que chegar durante a janela e faz um único fsync por lote
+fn run_writer(
+    file: File,
+    mut receiver: mpsc::UnboundedReceiver<JournalCommand>,
+    flush_interval: Duration,
+) {
+    let mut writer = BufWriter::new(file);
+
+    while let Some(first) = receiver.blocking_recv() {
+        if !flush_interval.is_zero() {
+            std::thread::sleep(flush_interval);
+        }
+
+        let mut batch = vec![first];
+        while let Ok(command) = receiver.try_recv() {
+            batch.push(command);
+        }
+
+        let mut acks = Vec::with_capacity(batch.len());
+        let mut result = Ok(());
+        for command in batch {
+            match command {
+                JournalCommand::Append(entry, ack) => {
+                    if result.is_ok() {
+                        result = write_record(&mut writer, &entry);
+                    }
+                    acks.extend(ack);
+                }
+                JournalCommand::Flush(ack) => acks.push(ack),
+            }
+        }
+
+        let result = result
+            .and_then(|_| writer.flush())
+            .and_then(|_| writer.get_ref().sync_data());
+
+        match result {
+            // Acks descartados fazem append_durable falhar para quem está esperando
+            Ok(()) => acks.into_iter().for_each(|ack| {
+                let _ = ack.send(());
+            }),
+            Err(e) => error!("Failed to write payment journal batch: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_replay_rebuilds_latest_state() {
+        let path = std::env::temp_dir().join(format!("payments-{}.journal", std::process::id()));
+        let id = |n: u8| format!("00000000-0000-4000-8000-00000000000{}", n);
+        let request = |n: u8| PaymentRequest { id: id(n), amount: 1990 };
+        let entries = vec![
+            JournalEntry::Admitted { request: request(1) },
+            JournalEntry::Purged,
+            JournalEntry::Admitted { request: request(2) },
+            JournalEntry::Admitted { request: request(3) },
+            JournalEntry::Admitted { request: request(4) },
+            JournalEntry::Released { id: id(4) },
+            JournalEntry::Completed {
+                payment: Payment {
+                    id: id(2),
+                    amount: 1990,
+                    processor: "default".to_string(),
+                    fee: 100,
+                    processed_at: Some(SystemTime::now()),
+                },
+            },
+        ];
+
+        let mut file = File::create(&path).unwrap();
+        for entry in &entries {
+            write_record(&mut file, entry).unwrap();
+        }
+        file.write_all(b"{\"type\":\"admit").unwrap(); // linha truncada
+
+        let payments = replay(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(payments.len(), 2);
+        assert_eq!(payments[0].id, id(2));
+        assert_eq!(payments[0].processor, "default");
+        assert_eq!(payments[0].fee, 100);
+        assert_eq!(payments[1].id, id(3));
+        assert!(payments[1].processed_at.is_none());
+    }
+}
@@ -1,67 +1,141 @@
 use crate::app::config::Config;
-use crate::models::payment::{PaymentRequest, Payment};
-use reqwest::Client;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::sync::{Arc, Mutex};
+use crate::services::circuit_breaker::{
+    CircuitBreakerConfig, CircuitBreakerSnapshot, CircuitBreakerState,
+};
+use crate::models::payment::{PaymentRequest, Payment, ProcessorPayload};
+use crate::services::concurrency_limiter::{LimiterOutcome, LimiterSnapshot};
+use crate::services::fee_router::FeeAwareRouter;
+use crate::services::processor_registry::{ProcessorRegistry, RegisteredProcessor};
+use crate::services::health_lease::{HealthLease, LeaseOutcome, LeaseRole};
+use crate::services::health_monitor::{HealthMonitor, ProcessorHealth};
+use crate::services::payment_service::ProcessorSummary;
+use crate::services::smart_fallback::{ProcessorStats, SmartFallbackManager};
+use crate::utils::time::format_timestamp;
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{info, error, warn};
 
 #[derive(Debug, Clone)]
-pub enum CircuitBreakerState {
-    Closed,
-    Open,
-    HalfOpen,
+pub enum ProcessorError {
+    Timeout,
+    Connection(String),
+    // 422: o processor já registrou esse correlationId
+    AlreadyProcessed,
+    RateLimited,
+    ClientError(StatusCode),
+    ServerError(StatusCode),
+    CircuitOpen,
+    // Limite de concorrência cheio e o breaker abriu durante a espera
+    Saturated,
+    UnknownProcessor,
 }
 
-struct CircuitBreaker {
-    state: CircuitBreakerState,
-    failure_count: u32,
-    last_failure_time: Option<SystemTime>,
-    threshold: u32,
-    timeout: Duration,
+impl ProcessorError {
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNPROCESSABLE_ENTITY => Self::AlreadyProcessed,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            StatusCode::REQUEST_TIMEOUT => Self::Timeout,
+            s if s.is_client_error() => Self::ClientError(s),
+            s => Self::ServerError(s),
+        }
+    }
+
+    // Erros que indicam problema no processor e devem pesar no circuit breaker
+    pub fn counts_against_health(&self) -> bool {
+        matches!(self, Self::Timeout | Self::Connection(_) | Self::RateLimited | Self::ServerError(_))
+    }
+
+    // Erros do nosso lado (payload rejeitado) não mudam em outra tentativa
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Self::ClientError(_) | Self::UnknownProcessor)
+    }
 }
 
-impl CircuitBreaker {
-    fn new(threshold: u32, timeout_secs: u64) -> Self {
-        Self {
-            state: CircuitBreakerState::Closed,
-            failure_count: 0,
-            last_failure_time: None,
-            threshold,
-            timeout: Duration::from_secs(timeout_secs),
-        }
-    }
-
-    fn can_execute(&mut self) -> bool {
-        match self.state {
-            CircuitBreakerState::Closed => true,
-            CircuitBreakerState::Open => {
-                if let Some(last_failure) = self.last_failure_time {
-                    if last_failure.elapsed().unwrap_or(Duration::ZERO) >= self.timeout {
-                        self.state = CircuitBreakerState::HalfOpen;
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            CircuitBreakerState::HalfOpen => true,
+impl fmt::Display for ProcessorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "request timed out"),
+            Self::Connection(e) => write!(f, "connection error: {}", e),
+            Self::AlreadyProcessed => write!(f, "payment already processed (HTTP 422)"),
+            Self::RateLimited => write!(f, "rate limited (HTTP 429)"),
+            Self::ClientError(status) => write!(f, "request rejected (HTTP {})", status),
+            Self::ServerError(status) => write!(f, "processor error (HTTP {})", status),
+            Self::CircuitOpen => write!(f, "circuit breaker open"),
+            Self::Saturated => write!(f, "concurrency limit reached"),
+            Self::UnknownProcessor => write!(f, "unknown processor"),
         }
     }
+}
+
+impl std::error::Error for ProcessorError {}
 
-    fn record_success(&mut self) {
-        self.failure_count = 0;
-        self.state = CircuitBreakerState::Closed;
-        self.last_failure_time = None;
+fn map_transport_error(e: reqwest::Error) -> ProcessorError {
+    if e.is_timeout() {
+        ProcessorError::Timeout
+    } else {
+        ProcessorError::Connection(e.to_string())
     }
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessorErrorStats {
+    timeouts: AtomicU64,
+    connection_errors: AtomicU64,
+    client_errors: AtomicU64,
+    server_errors: AtomicU64,
+    rate_limited: AtomicU64,
+    already_processed: AtomicU64,
+}
 
-    fn record_failure(&mut self) {
-        self.failure_count += 1;
-        self.last_failure_time = Some(SystemTime::now());
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessorErrorCounts {
+    pub timeouts: u64,
+    pub connection_errors: u64,
+    pub client_errors: u64,
+    pub server_errors: u64,
+    pub rate_limited: u64,
+    pub already_processed: u64,
+}
 
-        if self.failure_count >= self.threshold {
-            self.state = CircuitBreakerState::Open;
+impl ProcessorErrorStats {
+    pub fn record(&self, error: &ProcessorError) {
+        let counter = match error {
+            ProcessorError::Timeout => &self.timeouts,
+            ProcessorError::Connection(_) => &self.connection_errors,
+            ProcessorError::ClientError(_) => &self.client_errors,
+            ProcessorError::ServerError(_) => &self.server_errors,
+            ProcessorError::RateLimited => &self.rate_limited,
+            ProcessorError::AlreadyProcessed => &self.already_processed,
+            ProcessorError::CircuitOpen | ProcessorError::Saturated | ProcessorError::UnknownProcessor => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ProcessorErrorCounts {
+        ProcessorErrorCounts {
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            connection_errors: self.connection_errors.load(Ordering::Relaxed),
+            client_errors: self.client_errors.load(Ordering::Relaxed),
+            server_errors: self.server_errors.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            already_processed: self.already_processed.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        for counter in [
+            &self.timeouts,
+            &self.connection_errors,
+            &self.client_errors,
+            &self.server_errors,
+            &self.rate_limited,
+            &self.already_processed,
+        ] {
+            counter.store(0, Ordering::Relaxed);
         }
     }
 }
@@ -69,8 +143,12 @@ impl CircuitBreaker {
 pub struct PaymentProcessorClient {
     client: Client,
     config: Config,
-    default_breaker: Arc<Mutex<CircuitBreaker>>,
-    fallback_breaker: Arc<Mutex<CircuitBreaker>>,
+    fallback_manager: SmartFallbackManager,
+    router: FeeAwareRouter,
+    registry: ProcessorRegistry,
+    health_monitor: HealthMonitor,
+    health_lease: Option<HealthLease>,
+    limiter_wait: Duration,
 }
 
 impl PaymentProcessorClient {
@@ -81,145 +159,398 @@ impl PaymentProcessorClient {
             .expect("Failed to create HTTP client");
 
         Self {
+            health_monitor: HealthMonitor::new(client.clone()),
             client,
             config: config.clone(),
-            default_breaker: Arc::new(Mutex::new(CircuitBreaker::new(
-                config.circuit_breaker_threshold,
-                config.circuit_breaker_timeout_secs,
-            ))),
-            fallback_breaker: Arc::new(Mutex::new(CircuitBreaker::new(
-                config.circuit_breaker_threshold,
-                config.circuit_breaker_timeout_secs,
-            ))),
+            fallback_manager: SmartFallbackManager::new(
+                CircuitBreakerConfig::from_settings(&config.circuit_breaker),
+                config.processors.iter()
+                    .map(|p| (p.name.clone(), CircuitBreakerConfig::from_settings(&p.circuit_breaker)))
+                    .collect(),
+            ),
+            router: FeeAwareRouter::new(config),
+            registry: ProcessorRegistry::from_config(config),
+            health_lease: HealthLease::from_config(config),
+            limiter_wait: Duration::from_millis(config.concurrency_acquire_timeout_ms),
         }
     }
 
-    pub async fn process_payment(&self, request: PaymentRequest) -> Option<Payment> {
-        // Try default processor first
-        if let Some(payment) = self.try_processor("default", &request).await {
-            return Some(payment);
-        }
+    pub async fn process_payment(&self, request: PaymentRequest) -> Result<Payment, ProcessorError> {
+        let order = self.processor_order(request.amount).await;
 
-        // Fallback to secondary processor
-        if let Some(payment) = self.try_processor("fallback", &request).await {
-            return Some(payment);
+        let mut last_error = ProcessorError::UnknownProcessor;
+        for processor in order {
+            match self.try_processor(processor, &request).await {
+                Ok(payment) => return Ok(payment),
+                // Payload rejeitado: o outro processor também rejeitaria
+                Err(e) if !e.is_retryable() => return Err(e),
+                Err(e) => last_error = e,
+            }
         }
 
-        error!("Both processors failed for payment {}", request.id);
-        None
+        error!("All processors failed for payment {}: {}", request.id, last_error);
+        Err(last_error)
     }
 
-    async fn try_processor(&self, processor_type: &str, request: &PaymentRequest) -> Option<Payment> {
-        let (url, breaker) = match processor_type {
-            "default" => (&self.config.default_processor_url, &self.default_breaker),
-            "fallback" => (&self.config.fallback_processor_url, &self.fallback_breaker),
-            _ => return None,
+    // Com mais de um processor disponível (breaker fechado ou half-open), o
+    // FeeAwareRouter escolhe pelo custo esperado; com um só, vai nele.
+    // Processors que o service-health marca como failing ficam fora da
+    // escolha enquanto houver alternativa
+    async fn processor_order(&self, amount: u64) -> Vec<&RegisteredProcessor> {
+        let healthy: Vec<&RegisteredProcessor> = self.registry.iter()
+            .filter(|processor| !self.health_monitor.is_failing(processor.name()))
+            .collect();
+        let candidates = if healthy.is_empty() {
+            self.registry.iter().collect()
+        } else {
+            healthy
         };
 
-        // Check circuit breaker
-        let can_execute = {
-            let mut breaker = breaker.lock().unwrap();
-            breaker.can_execute()
+        let mut available = Vec::new();
+        for processor in &candidates {
+            if self.fallback_manager.is_processor_available(processor.name()).await {
+                available.push(&processor.config);
+            }
+        }
+
+        // Com todos os breakers abertos fica a ordem de prioridade
+        let first = match available.as_slice() {
+            [] => None,
+            [only] => Some(only.name.clone()),
+            _ => Some(self.router.decide(amount, &available, &self.health_monitor).await.processor),
+        };
+
+        let mut order: Vec<&RegisteredProcessor> = self.registry.iter().collect();
+        // sort estável: o escolhido vai para a frente, o resto mantém a prioridade
+        order.sort_by_key(|processor| first.as_deref() != Some(processor.name()));
+        order
+    }
+
+    async fn try_processor(&self, processor: &RegisteredProcessor, request: &PaymentRequest) -> Result<Payment, ProcessorError> {
+        let name = processor.name();
+        let errors = &processor.errors;
+
+        // Vaga no limite adaptativo antes do breaker, para que uma probe
+        // half-open não fique presa esperando na fila. Limite cheio é
+        // backpressure, não falha: espera a vaga enquanto o processor continua
+        // disponível, em vez de cair no próximo (mais caro) e gastar retries
+        let permit = loop {
+            if let Some(permit) = processor.limiter.acquire_timeout(self.limiter_wait).await {
+                break permit;
+            }
+            if !self.fallback_manager.is_processor_available(name).await {
+                warn!("Concurrency limit reached for {} processor and its breaker opened", name);
+                return Err(ProcessorError::Saturated);
+            }
         };
 
-        if !can_execute {
-            warn!("Circuit breaker open for {} processor", processor_type);
-            return None;
+        if !self.fallback_manager.try_acquire(name).await {
+            warn!("Circuit breaker open for {} processor", name);
+            return Err(ProcessorError::CircuitOpen);
         }
 
-        match self.send_request(url, request).await {
-            Ok(payment) => {
-                let mut breaker = breaker.lock().unwrap();
-                breaker.record_success();
-                info!("Payment {} processed successfully by {} processor", request.id, processor_type);
-                Some(payment)
+        let started = Instant::now();
+        // O processed_at local é o mesmo requestedAt que o processor usa nos
+        // filtros do summary dele, para as janelas baterem na reconciliação
+        let requested_at = SystemTime::now();
+        let result = match self.send_request(processor, request, requested_at).await {
+            // 422: já foi processado por esse processor numa tentativa anterior
+            Err(ProcessorError::AlreadyProcessed) => {
+                errors.record(&ProcessorError::AlreadyProcessed);
+                info!("Payment {} was already processed by {} processor", request.id, name);
+                Ok(())
+            }
+            other => other,
+        };
+
+        match result {
+            Ok(()) => {
+                permit.record(LimiterOutcome::Success);
+                let latency = started.elapsed();
+                let payment = self.build_payment(processor, request, requested_at);
+                self.fallback_manager.record_success(name, latency).await;
+                self.router.record(name, latency, true, payment.fee).await;
+                info!("Payment {} processed successfully by {} processor", request.id, name);
+                Ok(payment)
             }
             Err(e) => {
-                let mut breaker = breaker.lock().unwrap();
-                breaker.record_failure();
-                error!("Failed to process payment {} with {} processor: {}", request.id, processor_type, e);
-                None
+                errors.record(&e);
+                if e.counts_against_health() {
+                    permit.record(LimiterOutcome::Failure);
+                    self.fallback_manager.record_failure(name).await;
+                    self.router.record(name, started.elapsed(), false, 0).await;
+                } else {
+                    permit.record(LimiterOutcome::Ignored);
+                    self.fallback_manager.record_ignored(name).await;
+                }
+                error!("Failed to process payment {} with {} processor: {}", request.id, name, e);
+                Err(e)
             }
         }
     }
 
-    async fn send_request(&self, url: &str, request: &PaymentRequest) -> Result<Payment, Box<dyn std::error::Error + Send + Sync>> {
-        let payload = serde_json::json!({
-            "correlationId": request.id,
-            "amount": request.amount,
-            "requestedAt": SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64
-        });
+    async fn send_request(
+        &self,
+        processor: &RegisteredProcessor,
+        request: &PaymentRequest,
+        requested_at: SystemTime,
+    ) -> Result<(), ProcessorError> {
+        let payload = ProcessorPayload {
+            correlation_id: request.id.clone(),
+            amount: request.amount,
+            requested_at: format_timestamp(requested_at),
+        };
 
         let response = self.client
-            .post(&format!("{}/payments", url))
+            .post(format!("{}/payments", processor.url()))
+            .timeout(processor.timeout())
             .header("Content-Type", "application/json")
             .header("X-Rinha-Token", &self.config.token)
             .json(&payload)
             .send()
-            .await?;
+            .await
+            .map_err(map_transport_error)?;
 
         if response.status().is_success() {
-            let processor = if url.contains("default") {
-                "default"
-            } else {
-                "fallback"
-            };
-
-            Ok(Payment {
-                id: request.id.clone(),
-                amount: request.amount,
-                processor: processor.to_string(),
-                fee: request.amount / 20, // 5% fee
-                processed_at: Some(SystemTime::now()),
-            })
+            Ok(())
         } else {
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("HTTP {}", response.status())
-            )))
+            Err(ProcessorError::from_status(response.status()))
         }
     }
 
-    pub async fn health_check(&self, processor_type: &str) -> bool {
-        let url = match processor_type {
-            "default" => &self.config.default_processor_url,
-            "fallback" => &self.config.fallback_processor_url,
-            _ => return false,
-        };
+    fn build_payment(&self, processor: &RegisteredProcessor, request: &PaymentRequest, requested_at: SystemTime) -> Payment {
+        let fee = processor.config.fee.fee_for(request.amount);
+
+        Payment {
+            id: request.id.clone(),
+            amount: request.amount,
+            processor: processor.name().to_string(),
+            fee,
+            processed_at: Some(requested_at),
+        }
+    }
 
-        match self.client
-            .get(&format!("{}/health", url))
-            .timeout(Duration::from_millis(10000))
+    pub fn get_error_counts(&self, processor_type: &str) -> Option<ProcessorErrorCounts> {
+        self.registry.get(processor_type).map(|p| p.errors.snapshot())
+    }
+
+    pub fn processor_names(&self) -> Vec<String> {
+        self.registry.names()
+    }
+
+    fn processor_url(&self, processor_type: &str) -> Result<&str, ProcessorError> {
+        self.registry.get(processor_type)
+            .map(|p| p.url())
+            .ok_or(ProcessorError::UnknownProcessor)
+    }
+
+    // GET /admin/payments-summary: o que o processor diz ter recebido na janela
+    pub async fn fetch_admin_summary(
+        &self,
+        processor_type: &str,
+        from: Option<SystemTime>,
+        to: Option<SystemTime>,
+    ) -> Result<ProcessorSummary, ProcessorError> {
+        let url = self.processor_url(processor_type)?;
+
+        let mut query = Vec::new();
+        if let Some(from) = from {
+            query.push(("from", format_timestamp(from)));
+        }
+        if let Some(to) = to {
+            query.push(("to", format_timestamp(to)));
+        }
+
+        let response = self.client
+            .get(format!("{}/admin/payments-summary", url))
+            .header("X-Rinha-Token", &self.config.token)
+            .query(&query)
             .send()
             .await
-        {
-            Ok(response) => {
-                let is_healthy = response.status().is_success();
-                if is_healthy {
-                    info!("{} processor is healthy", processor_type);
-                } else {
-                    warn!("{} processor returned status: {}", processor_type, response.status());
+            .map_err(map_transport_error)?;
+
+        if !response.status().is_success() {
+            return Err(ProcessorError::from_status(response.status()));
+        }
+
+        response.json::<ProcessorSummary>()
+            .await
+            .map_err(|e| ProcessorError::Connection(format!("invalid summary payload: {}", e)))
+    }
+
+    // GET /payments/{id}: Ok(true) se o processor conhece o pagamento
+    pub async fn payment_exists(&self, processor_type: &str, correlation_id: &str) -> Result<bool, ProcessorError> {
+        let url = self.processor_url(processor_type)?;
+
+        let response = self.client
+            .get(format!("{}/payments/{}", url, correlation_id))
+            .header("X-Rinha-Token", &self.config.token)
+            .send()
+            .await
+            .map_err(map_transport_error)?;
+
+        match response.status() {
+            s if s.is_success() => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            s => Err(ProcessorError::from_status(s)),
+        }
+    }
+
+    pub async fn health_check(&self, processor_type: &str) -> bool {
+        let Some(url) = self.registry.get(processor_type).map(|p| p.url()) else {
+            return false;
+        };
+
+        match self.health_monitor.poll(processor_type, url).await {
+            Some(health) => !health.failing,
+            None => false,
+        }
+    }
+
+    // Com lease compartilhado só o líder consulta o service-health; as outras
+    // réplicas usam o que ele publicou
+    pub async fn refresh_health(&self) {
+        let Some(lease) = &self.health_lease else {
+            self.poll_health().await;
+            return;
+        };
+
+        match lease.acquire().await {
+            Ok(LeaseOutcome::Follower(published)) => {
+                for (processor, health) in published {
+                    self.health_monitor.apply(&processor, health);
+                }
+            }
+            Ok(LeaseOutcome::Leader) => {
+                self.poll_health().await;
+                if let Err(e) = lease.publish(&self.health_monitor.snapshot()).await {
+                    warn!("Failed to publish processor health: {}", e);
                 }
-                is_healthy
             }
             Err(e) => {
-                warn!("{} processor health check failed: {}", processor_type, e);
-                false
+                warn!("Health lease unavailable, polling processors directly: {}", e);
+                self.poll_health().await;
+            }
+        }
+    }
+
+    async fn poll_health(&self) {
+        for processor in self.registry.iter() {
+            self.health_check(processor.name()).await;
+        }
+    }
+
+    pub fn health_lease_role(&self) -> Option<LeaseRole> {
+        self.health_lease.as_ref().and_then(|lease| lease.role())
+    }
+
+    pub async fn release_health_lease(&self) {
+        if let Some(lease) = &self.health_lease {
+            if let Err(e) = lease.release().await {
+                warn!("Failed to release processor health lease: {}", e);
             }
         }
     }
 
+    // Último estado conhecido, sem chamar o processor
+    pub fn get_health(&self, processor_type: &str) -> Option<ProcessorHealth> {
+        self.health_monitor.get(processor_type)
+    }
+
+    pub async fn reset_breakers(&self) {
+        self.fallback_manager.reset().await;
+        self.router.reset();
+        for processor in self.registry.iter() {
+            processor.errors.reset();
+            processor.limiter.reset();
+        }
+    }
+
+    pub fn get_concurrency_snapshot(&self, processor_type: &str) -> Option<LimiterSnapshot> {
+        self.registry.get(processor_type).map(|processor| processor.limiter.snapshot())
+    }
+
+    // Quantas chamadas os processors aceitam juntos agora, somando os limites
+    pub fn total_concurrency_limit(&self) -> usize {
+        self.registry.iter().map(|processor| processor.limiter.limit()).sum()
+    }
+
     pub async fn get_breaker_status(&self, processor_type: &str) -> Option<CircuitBreakerState> {
-        let breaker = match processor_type {
-            "default" => &self.default_breaker,
-            "fallback" => &self.fallback_breaker,
-            _ => return None,
-        };
+        self.fallback_manager
+            .get_processor_stats(processor_type)
+            .await
+            .map(|stats| stats.circuit_breaker_state)
+    }
+
+    pub async fn get_breaker_snapshot(&self, processor_type: &str) -> Option<CircuitBreakerSnapshot> {
+        self.fallback_manager.breaker_snapshot(processor_type).await
+    }
 
-        let breaker = breaker.lock().unwrap();
-        Some(breaker.state.clone())
+    pub async fn get_routing_status(&self) -> serde_json::Value {
+        self.router.status(&self.registry.names()).await
     }
-}
\ No newline at end of file
+
+    pub async fn get_routing_stats(&self, processor_type: &str) -> Option<ProcessorStats> {
+        self.fallback_manager.get_processor_stats(processor_type).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_processor_error_classification() {
+        let duplicate = ProcessorError::from_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(matches!(duplicate, ProcessorError::AlreadyProcessed));
+        assert!(!duplicate.counts_against_health());
+
+        let bad_request = ProcessorError::from_status(StatusCode::BAD_REQUEST);
+        assert!(!bad_request.counts_against_health());
+        assert!(!bad_request.is_retryable());
+
+        let server_error = ProcessorError::from_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(server_error.counts_against_health());
+        assert!(server_error.is_retryable());
+
+        assert!(ProcessorError::Timeout.counts_against_health());
+        assert!(ProcessorError::CircuitOpen.is_retryable());
+        assert!(ProcessorError::Saturated.is_retryable());
+        assert!(!ProcessorError::Saturated.counts_against_health());
+    }
+
+    #[tokio::test]
+    async fn test_saturated_processor_waits_instead_of_failing_over() {
+        let app = axum::Router::new().route(
+            "/payments",
+            axum::routing::post(|| async {
+                tokio::time::sleep(Duration::from_millis(150)).await;
+                axum::http::StatusCode::OK
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut config = Config::from_env().unwrap();
+        for processor in &mut config.processors {
+            processor.url = url.clone();
+        }
+        config.health_lease_path = None;
+        config.concurrency_limit_initial = 1;
+        config.concurrency_limit_min = 1;
+        config.concurrency_limit_max = 1;
+        config.concurrency_acquire_timeout_ms = 20;
+        let client = PaymentProcessorClient::new(&config);
+
+        let request = |id: &str| PaymentRequest { id: id.to_string(), amount: 1000 };
+        let (first, second) = tokio::join!(
+            client.process_payment(request("first")),
+            client.process_payment(request("second")),
+        );
+
+        // O segundo espera a vaga do default em vez de ir para o fallback
+        assert_eq!(first.unwrap().processor, "default");
+        assert_eq!(second.unwrap().processor, "default");
+    }
+}
@@ -1,19 +1,48 @@
+use crate::app::config::Config;
 use crate::models::payment::{Payment, PaymentRequest};
+use crate::queue::QueuedPayment;
 use crate::services::payment_processor_client::PaymentProcessorClient;
 use crate::services::atomic_metrics::AtomicMetrics;
-use dashmap::DashMap;
+use crate::services::peer_summary::{merge_payments, ClusterSummary, PeerSummaryClient};
+use crate::services::payment_store::PaymentStore;
+use crate::services::payment_journal::{JournalEntry, PaymentJournal};
+use crate::services::reconciler::{ProcessorReconciliation, ReconciliationReport};
+use crate::services::retry_scheduler::{RetryDecision, RetryPolicy, RetryScheduler};
+use crate::utils::money::decimal_cents;
+use dashmap::DashSet;
+use futures::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::{SendTimeoutError, TrySendError};
+use tokio::sync::{mpsc, Notify, RwLock, Semaphore};
 use tracing::{info, warn};
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
-pub type PaymentStorage = Arc<DashMap<String, Payment>>;
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct SummaryFilters {
-    pub from_date: Option<String>,
-    pub to_date: Option<String>,
+    pub from_date: Option<SystemTime>,
+    pub to_date: Option<SystemTime>,
+}
+
+impl SummaryFilters {
+    // Janela inclusiva sobre o instante de processamento
+    pub fn contains(&self, processed_at: SystemTime) -> bool {
+        self.from_date.is_none_or(|from| processed_at >= from)
+            && self.to_date.is_none_or(|to| processed_at <= to)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ProcessorSummary {
+    #[serde(rename = "totalRequests")]
+    pub total_requests: u64,
+    // Vem também do /admin/payments-summary dos processors, somado em float
+    #[serde(rename = "totalAmount", serialize_with = "decimal_cents::serialize", deserialize_with = "decimal_cents::deserialize_rounded")]
+    pub total_amount_cents: u64,
+    #[serde(rename = "totalFee", serialize_with = "decimal_cents::serialize", deserialize_with = "decimal_cents::deserialize_rounded", default)]
+    pub total_fee_cents: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -23,117 +52,531 @@ pub struct SummaryResult {
     pub count: u64,
     pub count_processed: u64,
     pub count_failed: u64,
+    pub processors: BTreeMap<String, ProcessorSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeResult {
+    pub payments: u64,
+    pub queued_discarded: u64,
 }
 
 pub struct PaymentService {
-    storage: PaymentStorage,
+    config: Config,
+    storage: Arc<dyn PaymentStore>,
     processor_client: Arc<PaymentProcessorClient>,
-    payment_sender: mpsc::Sender<PaymentRequest>,
+    payment_sender: mpsc::Sender<QueuedPayment>,
     metrics: Arc<AtomicMetrics>,
+    retry_scheduler: RetryScheduler,
+    journal: Option<PaymentJournal>,
+    peers: PeerSummaryClient,
+    // Ids sendo processados agora, para nunca enviar o mesmo pagamento em paralelo
+    in_flight: DashSet<String>,
+    // Admissões e resultados seguram o lock de leitura; o purge segura o de escrita
+    admission_gate: RwLock<()>,
+    // Incrementada a cada purge; o que foi enfileirado antes é descartado
+    purge_generation: AtomicU64,
+    accepting: AtomicBool,
+    shutdown: Notify,
 }
 
 #[derive(Debug)]
 pub enum ServiceError {
     QueueFull,
-    ProcessingError,
+    QueueClosed,
+    PersistenceError,
+    Duplicate(Payment),
 }
 
 impl PaymentService {
     pub fn new(
-        storage: PaymentStorage,
+        config: &Config,
+        storage: Arc<dyn PaymentStore>,
         processor_client: Arc<PaymentProcessorClient>,
-        payment_sender: mpsc::Sender<PaymentRequest>,
+        payment_sender: mpsc::Sender<QueuedPayment>,
+        journal: Option<PaymentJournal>,
     ) -> Self {
+        let metrics = Arc::new(AtomicMetrics::new());
+        let retry_scheduler = RetryScheduler::new(
+            RetryPolicy::from_config(config),
+            payment_sender.clone(),
+            metrics.clone(),
+        );
+
         Self {
+            config: config.clone(),
             storage,
             processor_client,
             payment_sender,
-            metrics: Arc::new(AtomicMetrics::new()),
+            metrics,
+            retry_scheduler,
+            journal,
+            peers: PeerSummaryClient::from_config(config),
+            in_flight: DashSet::new(),
+            admission_gate: RwLock::new(()),
+            purge_generation: AtomicU64::new(0),
+            accepting: AtomicBool::new(true),
+            shutdown: Notify::new(),
         }
     }
 
+    pub fn is_admin_token_valid(&self, token: Option<&str>) -> bool {
+        token.is_some_and(|token| constant_time_eq(token.as_bytes(), self.config.token.as_bytes()))
+    }
+
     pub async fn submit_payment(&self, request: PaymentRequest) -> Result<(), ServiceError> {
-        self.metrics.increment_submitted();
-        
-        let payment = Payment {
+        let _admission = self.admission_gate.read().await;
+
+        if !self.accepting.load(Ordering::Acquire) {
+            return Err(ServiceError::QueueClosed);
+        }
+
+        // Admissão idempotente: o mesmo correlationId nunca é reenviado ao processor
+        let pending = Payment {
             id: request.id.clone(),
             amount: request.amount,
             processor: "pending".to_string(),
             fee: 0,
             processed_at: None,
         };
-        
-        self.storage.insert(request.id.clone(), payment);
-        
-        self.payment_sender.send(request).await
-            .map_err(|_| ServiceError::QueueFull)?;
-        
+        if let Err(existing) = self.storage.insert(pending) {
+            self.metrics.increment_duplicates();
+            return Err(ServiceError::Duplicate(existing));
+        }
+
+        let id = request.id.clone();
+
+        // Write-ahead: só confirma o pagamento depois do fsync da admissão
+        if let Some(journal) = &self.journal {
+            let entry = JournalEntry::Admitted { request: request.clone() };
+            if journal.append_durable(entry).await.is_err() {
+                self.storage.remove(&id);
+                return Err(ServiceError::PersistenceError);
+            }
+        }
+
+        // O lock de leitura garante que nenhum purge muda a geração até o item entrar na fila
+        let queued = QueuedPayment { request, generation: self.current_generation() };
+        if let Err(e) = self.enqueue(queued).await {
+            // Libera o id para que o cliente possa tentar novamente
+            self.storage.remove(&id);
+            if let Some(journal) = &self.journal {
+                journal.append(JournalEntry::Released { id: id.clone() });
+            }
+            if matches!(e, ServiceError::QueueFull) {
+                self.metrics.increment_rejected();
+            }
+            return Err(e);
+        }
+
+        self.metrics.increment_submitted();
         Ok(())
     }
 
-    pub async fn get_summary(&self, _filters: SummaryFilters) -> SummaryResult {
-        let mut total_amount = 0u64;
-        let mut total_fee = 0u64;
-        let mut count = 0u64;
-        let mut count_processed = 0u64;
-        let mut count_failed = 0u64;
-
-        for payment in self.storage.iter() {
-            count += 1;
-            if payment.processed_at.is_some() {
-                total_amount += payment.amount;
-                total_fee += payment.fee;
-                if payment.processor != "failed" {
-                    count_processed += 1;
-                } else {
-                    count_failed += 1;
+    // Admissão sem bloqueio: tenta na hora e, se a fila estiver cheia,
+    // espera no máximo admission_wait_ms antes de rejeitar
+    async fn enqueue(&self, queued: QueuedPayment) -> Result<(), ServiceError> {
+        let queued = match self.payment_sender.try_send(queued) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Closed(_)) => return Err(ServiceError::QueueClosed),
+            Err(TrySendError::Full(queued)) => queued,
+        };
+
+        if self.config.admission_wait_ms == 0 {
+            return Err(ServiceError::QueueFull);
+        }
+
+        let budget = Duration::from_millis(self.config.admission_wait_ms);
+        match self.payment_sender.send_timeout(queued, budget).await {
+            Ok(()) => Ok(()),
+            Err(SendTimeoutError::Timeout(_)) => Err(ServiceError::QueueFull),
+            Err(SendTimeoutError::Closed(_)) => Err(ServiceError::QueueClosed),
+        }
+    }
+
+    fn current_generation(&self) -> u64 {
+        self.purge_generation.load(Ordering::Acquire)
+    }
+
+    pub fn retry_after_secs(&self) -> u64 {
+        self.config.admission_retry_after_secs
+    }
+
+    pub async fn get_summary(&self, filters: SummaryFilters) -> SummaryResult {
+        self.storage.aggregate(filters, &self.processor_names())
+    }
+
+    // Pagamentos aceitos por um processor dentro da janela; é o que os peers
+    // consultam para montar o summary do cluster
+    pub fn processed_payments(&self, filters: SummaryFilters) -> Vec<Payment> {
+        let mut payments = self.storage.range(filters);
+        payments.retain(|payment| payment.processor != "failed");
+        payments
+    }
+
+    // Pagamentos locais somados aos das réplicas que responderam, sem contar
+    // duas vezes o mesmo correlationId; quem decide o que fazer com um
+    // resultado parcial é quem chama
+    pub async fn get_cluster_summary(&self, filters: SummaryFilters) -> ClusterSummary {
+        // Sem peers não há o que deduplicar: soma direto no store
+        if !self.peers.has_peers() {
+            let totals = self.get_summary(filters).await.processors;
+            return ClusterSummary { totals, unreachable_peers: Vec::new() };
+        }
+
+        let mut replicas = vec![self.processed_payments(filters)];
+        let mut unreachable_peers = Vec::new();
+
+        for (peer, result) in self.peers.fetch(filters).await {
+            match result {
+                Ok(payments) => replicas.push(payments),
+                Err(e) => {
+                    warn!("Failed to fetch summary from peer {}: {}", peer, e);
+                    unreachable_peers.push(format!("{}: {}", peer, e));
                 }
             }
         }
 
-        SummaryResult {
-            total_amount_cents: total_amount,
-            total_fee_cents: total_fee,
-            count,
-            count_processed,
-            count_failed,
+        let totals = merge_payments(&self.processor_names(), replicas);
+        ClusterSummary { totals, unreachable_peers }
+    }
+
+    pub fn summary_requires_all_peers(&self) -> bool {
+        self.peers.require_all_peers()
+    }
+
+    pub async fn process_payments_async(self: Arc<Self>, mut receiver: mpsc::Receiver<QueuedPayment>) {
+        let workers = self.config.worker_concurrency.max(1);
+        info!("Starting payment processor with {} concurrent workers", workers);
+
+        let semaphore = Arc::new(Semaphore::new(workers));
+        let mut closing = false;
+
+        loop {
+            // Só consome da fila quando há um worker livre
+            let permit = semaphore.clone()
+                .acquire_owned()
+                .await
+                .expect("worker semaphore closed");
+
+            let request = tokio::select! {
+                biased;
+                _ = self.shutdown.notified(), if !closing => {
+                    // Fecha a fila para novos envios, mas continua drenando o que já está nela
+                    receiver.close();
+                    closing = true;
+                    info!("Draining {} queued payments", receiver.len());
+                    continue;
+                }
+                request = receiver.recv() => request,
+            };
+
+            let Some(queued) = request else {
+                break;
+            };
+
+            let service = self.clone();
+            tokio::spawn(async move {
+                service.process_single_payment(queued).await;
+                drop(permit);
+            });
         }
+
+        // Canal fechado: espera os workers em andamento terminarem
+        let _ = semaphore.acquire_many(workers as u32).await;
+        info!("Payment processor stopped");
     }
 
-    pub async fn process_payments_async(&self, mut receiver: mpsc::Receiver<PaymentRequest>) {
-        info!("Starting payment processor worker");
-        
-        while let Some(request) = receiver.recv().await {
-            self.process_single_payment(request).await;
+    async fn process_single_payment(&self, queued: QueuedPayment) {
+        let request = &queued.request;
+
+        // Itens que ficaram na fila após um purge são descartados, mesmo que o
+        // mesmo id tenha sido reenviado depois dele
+        if queued.generation != self.current_generation() {
+            info!("Discarding payment {} queued before the last purge", request.id);
+            return;
+        }
+
+        match self.storage.get(&request.id).map(|payment| payment.processed_at.is_some()) {
+            None => {
+                info!("Discarding purged payment: {}", request.id);
+                return;
+            }
+            Some(true) => {
+                info!("Payment {} already finished, skipping", request.id);
+                return;
+            }
+            Some(false) => {}
+        }
+
+        if !self.in_flight.insert(request.id.clone()) {
+            warn!("Payment {} is already being processed, skipping", request.id);
+            return;
+        }
+
+        let retry = self.process_claimed_payment(request).await;
+        // Libera o id antes de reagendar: o retry não pode achar o pagamento em andamento
+        self.in_flight.remove(&request.id);
+        if retry {
+            self.schedule_retry(queued).await;
         }
     }
 
-    async fn process_single_payment(&self, request: PaymentRequest) {
+    // true se a falha foi transitória e vale tentar de novo
+    async fn process_claimed_payment(&self, request: &PaymentRequest) -> bool {
         info!("Processing payment: {}", request.id);
         
         match self.processor_client.process_payment(request.clone()).await {
-            Some(processed_payment) => {
-                self.storage.insert(request.id.clone(), processed_payment);
-                self.metrics.increment_processed();
-                info!("Payment {} processed successfully", request.id);
+            Ok(processed_payment) => {
+                self.retry_scheduler.clear(&request.id);
+                if self.store_result(processed_payment).await {
+                    self.metrics.increment_processed();
+                    info!("Payment {} processed successfully", request.id);
+                }
+                false
             }
-            None => {
-                let failed_payment = Payment {
-                    id: request.id.clone(),
-                    amount: request.amount,
-                    processor: "failed".to_string(),
-                    fee: 0,
-                    processed_at: Some(SystemTime::now()),
-                };
-                self.storage.insert(request.id, failed_payment);
-                self.metrics.increment_failed();
-                warn!("Payment processing failed");
+            Err(e) if !e.is_retryable() => {
+                self.retry_scheduler.clear(&request.id);
+                self.mark_failed(request).await;
+                warn!("Payment {} rejected by processor: {}", request.id, e);
+                false
+            }
+            Err(_) => true,
+        }
+    }
+
+    // Os processors costumam voltar em segundos: tenta de novo antes de desistir
+    async fn schedule_retry(&self, queued: QueuedPayment) {
+        let request = queued.request.clone();
+        let decision = self.retry_scheduler.schedule(queued);
+
+        match decision {
+            RetryDecision::Scheduled { attempt, delay } => {
+                info!("Retrying payment {} in {:?} (attempt {})", request.id, delay, attempt + 1);
             }
+            RetryDecision::Exhausted { attempts } => {
+                self.mark_failed(&request).await;
+                warn!("Payment {} failed after {} attempts", request.id, attempts);
+            }
+        }
+    }
+
+    async fn mark_failed(&self, request: &PaymentRequest) {
+        let failed_payment = Payment {
+            id: request.id.clone(),
+            amount: request.amount,
+            processor: "failed".to_string(),
+            fee: 0,
+            processed_at: Some(SystemTime::now()),
+        };
+        if self.store_result(failed_payment).await {
+            self.metrics.increment_failed();
+        }
+    }
+
+    // Janela da reconciliação periódica: todo o histórico até alguns segundos
+    // atrás, para não contar como drift o que ainda está em andamento
+    pub fn settled_filters(&self) -> SummaryFilters {
+        let settle = Duration::from_secs(self.config.reconcile_settle_secs);
+        SummaryFilters {
+            from_date: None,
+            to_date: SystemTime::now().checked_sub(settle),
+        }
+    }
+
+    // Compara nossos totais por processor com o /admin/payments-summary de cada um.
+    // Com check_ids, consulta pagamento a pagamento (até reconcile_max_id_checks).
+    pub async fn reconcile(&self, filters: SummaryFilters, check_ids: bool) -> ReconciliationReport {
+        // O processor enxerga os pagamentos de todas as réplicas
+        let cluster = self.get_cluster_summary(filters).await;
+        let peer_error = cluster.is_partial()
+            .then(|| format!("peer summary unavailable: {}", cluster.unreachable_peers.join("; ")));
+        let mut local = cluster.totals;
+        let mut processors = Vec::new();
+
+        for processor in self.config.processors.iter().map(|p| p.name.as_str()) {
+            let local_summary = local.remove(processor).unwrap_or_default();
+            let remote = self.processor_client
+                .fetch_admin_summary(processor, filters.from_date, filters.to_date)
+                .await
+                .map_err(|e| e.to_string());
+
+            let mut reconciliation = ProcessorReconciliation::new(processor, local_summary, remote);
+            if reconciliation.error.is_none() {
+                reconciliation.error = peer_error.clone();
+            }
+            if check_ids {
+                let (missing_on_processor, missing_locally) = self.find_missing_ids(processor, filters).await;
+                reconciliation.missing_on_processor = Some(missing_on_processor);
+                reconciliation.missing_locally = Some(missing_locally);
+            }
+            processors.push(reconciliation);
+        }
+
+        ReconciliationReport::new(filters.from_date, filters.to_date, processors)
+    }
+
+    async fn find_missing_ids(&self, processor: &str, filters: SummaryFilters) -> (Vec<String>, Vec<String>) {
+        // Pagamentos atribuídos a esse processor e os que falharam em todos.
+        // Pendentes (na fila, em andamento ou esperando retry) ainda podem
+        // chegar ao processor e ficam de fora
+        let candidates: Vec<(String, bool)> = self.storage.range(filters)
+            .into_iter()
+            .filter_map(|p| match p.processor.as_str() {
+                name if name == processor => Some((p.id, true)),
+                "failed" => Some((p.id, false)),
+                _ => None,
+            })
+            .take(self.config.reconcile_max_id_checks)
+            .collect();
+
+        let results: Vec<(String, bool, Result<bool, _>)> = stream::iter(candidates)
+            .map(|(id, ours)| async move {
+                let exists = self.processor_client.payment_exists(processor, &id).await;
+                (id, ours, exists)
+            })
+            .buffer_unordered(16)
+            .collect()
+            .await;
+
+        let mut missing_on_processor = Vec::new();
+        let mut missing_locally = Vec::new();
+        for (id, ours, exists) in results {
+            match (ours, exists) {
+                (true, Ok(false)) => missing_on_processor.push(id),
+                (false, Ok(true)) => missing_locally.push(id),
+                (_, Err(e)) => warn!("Could not check payment {} on {} processor: {}", id, processor, e),
+                _ => {}
+            }
+        }
+
+        (missing_on_processor, missing_locally)
+    }
+
+    // Só atualiza pagamentos que ainda existem (não foram removidos por um purge).
+    // O gate garante que o Completed nunca vá para o journal depois de um Purged.
+    async fn store_result(&self, payment: Payment) -> bool {
+        let _gate = self.admission_gate.read().await;
+        if !self.storage.update_state(payment.clone()) {
+            return false;
+        }
+        if let Some(journal) = &self.journal {
+            journal.append(JournalEntry::Completed { payment });
+        }
+        true
+    }
+
+    // Reconstrói o storage a partir do journal e reenfileira o que não terminou
+    // (inclusive pendentes que um store em disco já trazia).
+    // Deve rodar depois que o worker começou a consumir a fila.
+    pub async fn restore(&self, payments: Vec<Payment>) -> usize {
+        for payment in payments {
+            self.storage.upsert(payment);
+        }
+        if self.storage.is_empty() {
+            return 0;
+        }
+
+        let generation = self.current_generation();
+        let mut pending = Vec::new();
+        self.storage.for_each(&mut |payment| {
+            if payment.processed_at.is_none() {
+                let request = PaymentRequest { id: payment.id.clone(), amount: payment.amount };
+                pending.push(QueuedPayment { request, generation });
+            }
+        });
+
+        let mut requeued = 0;
+        for queued in pending {
+            if self.payment_sender.send(queued).await.is_err() {
+                warn!("Queue closed while restoring journaled payments");
+                break;
+            }
+            requeued += 1;
+        }
+
+        info!("Restored {} payments, {} re-enqueued", self.storage.len(), requeued);
+        requeued
+    }
+
+    // Para de aceitar pagamentos e avisa o worker para drenar a fila
+    pub fn begin_shutdown(&self) {
+        if self.accepting.swap(false, Ordering::AcqRel) {
+            info!("Shutting down: no longer accepting payments");
+            self.shutdown.notify_one();
+        }
+    }
+
+    // Chamado depois do dreno (ou do deadline): persiste e registra o que sobrou
+    pub async fn complete_shutdown(&self) {
+        let mut pending: Vec<String> = Vec::new();
+        self.storage.for_each(&mut |payment| {
+            if payment.processed_at.is_none() {
+                pending.push(payment.id.clone());
+            }
+        });
+
+        if pending.is_empty() {
+            info!("All payments processed before shutdown");
+        } else {
+            warn!(
+                "{} payments left unprocessed at shutdown ({} in flight, {} awaiting retry): {:?}",
+                pending.len(),
+                self.in_flight.len(),
+                self.retry_scheduler.pending(),
+                pending
+            );
+        }
+
+        self.processor_client.release_health_lease().await;
+
+        if let Some(journal) = &self.journal {
+            match journal.flush().await {
+                Ok(()) => info!("Payment journal flushed to {}", journal.path().display()),
+                Err(_) => warn!("Failed to flush payment journal on shutdown"),
+            }
+        }
+
+        // flush espera o writer do store; fora das threads do runtime
+        let storage = self.storage.clone();
+        match tokio::task::spawn_blocking(move || storage.flush()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to flush payment store on shutdown: {}", e),
+            Err(e) => warn!("Failed to flush payment store on shutdown: {}", e),
+        }
+    }
+
+    pub async fn purge(&self) -> PurgeResult {
+        let _admission = self.admission_gate.write().await;
+
+        let payments = self.storage.len() as u64;
+        let queued_discarded =
+            (self.payment_sender.max_capacity() - self.payment_sender.capacity()) as u64;
+
+        self.purge_generation.fetch_add(1, Ordering::AcqRel);
+        self.storage.clear();
+        if let Some(journal) = &self.journal {
+            if journal.append_durable(JournalEntry::Purged).await.is_err() {
+                warn!("Failed to record purge in payment journal");
+            }
+        }
+        self.metrics.reset();
+        self.retry_scheduler.clear_all();
+        self.processor_client.reset_breakers().await;
+
+        warn!(
+            "Purged {} payments ({} still queued will be discarded)",
+            payments, queued_discarded
+        );
+
+        PurgeResult {
+            payments,
+            queued_discarded,
         }
     }
 
     pub fn get_payment(&self, id: &str) -> Option<Payment> {
-        self.storage.get(id).map(|entry| entry.clone())
+        self.storage.get(id)
     }
 
     pub async fn get_metrics(&self) -> serde_json::Value {
@@ -141,31 +584,61 @@ impl PaymentService {
         let processed = self.metrics.get_processed();
         let failed = self.metrics.get_failed();
 
+        let mut processors = serde_json::Map::new();
+        for processor in self.config.processors.iter().map(|p| p.name.as_str()) {
+            processors.insert(processor.to_string(), self.get_processor_status(processor).await);
+        }
+
         serde_json::json!({
             "submitted": submitted,
             "processed": processed,
             "failed": failed,
+            "duplicates": self.metrics.get_duplicates(),
+            "rejected": self.metrics.get_rejected(),
+            "queue": {
+                "capacity": self.payment_sender.max_capacity(),
+                "queued": self.payment_sender.max_capacity() - self.payment_sender.capacity()
+            },
+            "retries": {
+                "scheduled": self.metrics.get_retries_scheduled(),
+                "exhausted": self.metrics.get_retries_exhausted(),
+                "pending": self.retry_scheduler.pending()
+            },
             "success_rate": if submitted > 0 { 
                 (processed as f64 / submitted as f64) * 100.0 
             } else { 
                 0.0 
             },
-            "processors": {
-                "default": self.get_processor_status("default").await,
-                "fallback": self.get_processor_status("fallback").await,
-            }
+            "processors": processors,
+            "routing": self.processor_client.get_routing_status().await,
+            "health_lease": self.processor_client.health_lease_role()
         })
     }
 
     async fn get_processor_status(&self, processor: &str) -> serde_json::Value {
-        let health = self.processor_client.health_check(processor).await;
-        let breaker_status = self.processor_client
-            .get_breaker_status(processor)
-            .await;
+        let health = self.processor_client.get_health(processor);
+        let routing = self.processor_client.get_routing_stats(processor).await;
 
         serde_json::json!({
-            "healthy": health,
-            "circuit_breaker": format!("{:?}", breaker_status)
+            "healthy": health.as_ref().map(|h| !h.failing),
+            "service_health": health.as_ref().map(|h| serde_json::json!({
+                "failing": h.failing,
+                "minResponseTime": h.min_response_time_ms,
+                "age_ms": h.age().as_millis() as u64
+            })),
+            "circuit_breaker": self.processor_client.get_breaker_snapshot(processor).await,
+            "concurrency": self.processor_client.get_concurrency_snapshot(processor),
+            "routing": routing.map(|stats| serde_json::json!({
+                "success_count": stats.success_count,
+                "failure_count": stats.failure_count,
+                "latency_avg_ms": stats.latency_avg.as_millis() as u64
+            })),
+            "errors": self.processor_client.get_error_counts(processor),
+            "fee": self.config.fee_for(processor).map(|fee| serde_json::json!({
+                "rate": fee.rate(),
+                "fixed_cents": fee.fixed_cents,
+                "rounding": fee.rounding
+            }))
         })
     }
 
@@ -175,15 +648,19 @@ impl PaymentService {
     }
 
     pub fn get_total_amount(&self) -> u64 {
-        self.storage.iter()
-            .map(|entry| entry.amount)
-            .sum()
+        let mut total = 0;
+        self.storage.for_each(&mut |payment| total += payment.amount);
+        total
     }
 
     pub fn get_total_fees(&self) -> u64 {
-        self.storage.iter()
-            .map(|entry| entry.fee)
-            .sum()
+        let mut total = 0;
+        self.storage.for_each(&mut |payment| total += payment.fee);
+        total
+    }
+
+    pub fn processor_names(&self) -> Vec<String> {
+        self.processor_client.processor_names()
     }
 
     pub async fn get_circuit_breaker_status(&self, processor: &str) -> String {
@@ -192,4 +669,165 @@ impl PaymentService {
             None => "unknown".to_string(),
         }
     }
-}
\ No newline at end of file
+}
+
+// Compara sem parar no primeiro byte diferente: o tempo de resposta não revela
+// quanto do token estava certo
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= (x ^ y) as usize;
+    }
+    diff == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::circuit_breaker::CircuitBreakerState;
+    use crate::services::payment_store::MemoryPaymentStore;
+
+    const ID: &str = "4a7901b8-7d26-4d9d-aa19-4dc1c7cf60b3";
+
+    // Processors que recusam conexão na hora: toda chamada vira retry
+    fn unreachable_config() -> Config {
+        let mut config = Config::from_env().unwrap();
+        for processor in &mut config.processors {
+            processor.url = "http://127.0.0.1:1".to_string();
+        }
+        config.retry_base_delay_ms = 300;
+        config.retry_max_delay_ms = 300;
+        config
+    }
+
+    fn service(config: &Config, journal: Option<PaymentJournal>) -> (Arc<PaymentService>, mpsc::Receiver<QueuedPayment>) {
+        let (sender, receiver) = crate::queue::create_queue(16);
+        let client = Arc::new(PaymentProcessorClient::new(config));
+        let storage = Arc::new(MemoryPaymentStore::new());
+        (Arc::new(PaymentService::new(config, storage, client, sender, journal)), receiver)
+    }
+
+    async fn wait_until(mut done: impl FnMut() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !done() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("condition not reached");
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_submit_is_rejected() {
+        let (payments, mut receiver) = service(&unreachable_config(), None);
+
+        payments.submit_payment(PaymentRequest { id: ID.to_string(), amount: 1990 }).await.unwrap();
+        let duplicate = payments.submit_payment(PaymentRequest { id: ID.to_string(), amount: 500 }).await;
+
+        // Devolve o que já foi admitido, sem enfileirar de novo
+        match duplicate {
+            Err(ServiceError::Duplicate(existing)) => assert_eq!(existing.amount, 1990),
+            other => panic!("expected duplicate, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(receiver.recv().await.unwrap().request.id, ID);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_pending_at_shutdown_is_replayed() {
+        let path = std::env::temp_dir().join(format!("retry-replay-{}.journal", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = unreachable_config();
+
+        let (journal, _) = PaymentJournal::open(&path, Duration::ZERO).unwrap();
+        let (payments, receiver) = service(&config, Some(journal));
+        let worker = tokio::spawn(payments.clone().process_payments_async(receiver));
+
+        payments.submit_payment(PaymentRequest { id: ID.to_string(), amount: 1990 }).await.unwrap();
+        wait_until(|| payments.retry_scheduler.pending() == 1).await;
+
+        // Shutdown antes do backoff vencer: o retry encontra a fila fechada
+        payments.begin_shutdown();
+        worker.await.unwrap();
+        wait_until(|| payments.retry_scheduler.pending() == 0).await;
+        payments.complete_shutdown().await;
+        assert!(payments.get_payment(ID).unwrap().processed_at.is_none());
+        drop(payments);
+
+        let (_, replayed) = PaymentJournal::open(&path, Duration::ZERO).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].id, ID);
+        assert!(replayed[0].processed_at.is_none());
+
+        let (restored, mut receiver) = service(&config, None);
+        assert_eq!(restored.restore(replayed).await, 1);
+        assert_eq!(receiver.recv().await.unwrap().request.id, ID);
+    }
+
+    #[tokio::test]
+    async fn test_payment_queued_before_purge_is_dropped() {
+        let (payments, mut receiver) = service(&unreachable_config(), None);
+
+        payments.submit_payment(PaymentRequest { id: ID.to_string(), amount: 1990 }).await.unwrap();
+        payments.purge().await;
+        payments.submit_payment(PaymentRequest { id: ID.to_string(), amount: 500 }).await.unwrap();
+
+        // O item de antes do purge é descartado mesmo com o id reenviado
+        let stale = receiver.recv().await.unwrap();
+        assert_eq!(stale.request.amount, 1990);
+        payments.process_single_payment(stale).await;
+        assert_eq!(payments.retry_scheduler.pending(), 0);
+
+        let current = receiver.recv().await.unwrap();
+        payments.process_single_payment(current).await;
+        assert_eq!(payments.retry_scheduler.pending(), 1);
+        assert_eq!(payments.get_payment(ID).unwrap().amount, 500);
+    }
+
+    #[tokio::test]
+    async fn test_purge_resets_metrics_retries_and_breakers() {
+        let mut config = unreachable_config();
+        for processor in &mut config.processors {
+            processor.circuit_breaker.min_requests = 1;
+        }
+        let (payments, mut receiver) = service(&config, None);
+
+        payments.submit_payment(PaymentRequest { id: ID.to_string(), amount: 1990 }).await.unwrap();
+        payments.process_single_payment(receiver.recv().await.unwrap()).await;
+        assert_eq!(payments.metrics.get_submitted(), 1);
+        assert_eq!(payments.retry_scheduler.pending(), 1);
+        let breaker = payments.processor_client.get_breaker_status("default").await;
+        assert_eq!(breaker, Some(CircuitBreakerState::Open));
+
+        let purged = payments.purge().await;
+        assert_eq!(purged.payments, 1);
+        assert_eq!(payments.metrics.get_submitted(), 0);
+        assert_eq!(payments.retry_scheduler.pending(), 0);
+        let breaker = payments.processor_client.get_breaker_status("default").await;
+        assert_eq!(breaker, Some(CircuitBreakerState::Closed));
+        assert!(payments.get_payment(ID).is_none());
+    }
+
+    #[test]
+    fn test_invalid_admin_token_is_rejected() {
+        let config = unreachable_config();
+        let (payments, _receiver) = service(&config, None);
+
+        assert!(payments.is_admin_token_valid(Some(&config.token)));
+        assert!(!payments.is_admin_token_valid(Some("wrong-token")));
+        assert!(!payments.is_admin_token_valid(Some("")));
+        assert!(!payments.is_admin_token_valid(None));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"123", b"123"));
+        assert!(!constant_time_eq(b"123", b"124"));
+        assert!(!constant_time_eq(b"123", b"1234"));
+        assert!(!constant_time_eq(b"", b"123"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
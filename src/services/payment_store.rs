@@ -0,0 +1,471 @@
+use crate::app::config::Config;
+use crate::models::payment::Payment;
+use crate::services::payment_service::{ProcessorSummary, SummaryFilters, SummaryResult};
+use crate::services::record_log::{self, write_record, CountingWriter};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info};
+
+/// Armazenamento dos pagamentos, independente do backend.
+pub trait PaymentStore: Send + Sync {
+    /// Insere só se o id estiver livre; senão devolve o pagamento existente
+    fn insert(&self, payment: Payment) -> Result<(), Payment>;
+    /// Grava o pagamento, existindo ou não
+    fn upsert(&self, payment: Payment);
+    /// Atualiza o estado de um pagamento que ainda existe (false se foi removido)
+    fn update_state(&self, payment: Payment) -> bool;
+    fn remove(&self, id: &str) -> Option<Payment>;
+    fn get(&self, id: &str) -> Option<Payment>;
+    fn len(&self) -> usize;
+    fn clear(&self);
+    fn for_each(&self, visit: &mut dyn FnMut(&Payment));
+
+    // Garante no disco o que já foi gravado (no-op para backends em memória)
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Pagamentos finalizados dentro da janela de processed_at
+    fn range(&self, filters: SummaryFilters) -> Vec<Payment> {
+        let mut payments = Vec::new();
+        self.for_each(&mut |payment| {
+            if payment.processed_at.is_some_and(|at| filters.contains(at)) {
+                payments.push(payment.clone());
+            }
+        });
+        payments
+    }
+
+    // Totais da janela, agrupados pelo processor que aceitou cada pagamento.
+    // Todo processor em `processors` aparece, mesmo sem pagamentos.
+    fn aggregate(&self, filters: SummaryFilters, processors: &[String]) -> SummaryResult {
+        let mut result = SummaryResult {
+            total_amount_cents: 0,
+            total_fee_cents: 0,
+            count: 0,
+            count_processed: 0,
+            count_failed: 0,
+            processors: processors.iter()
+                .map(|name| (name.clone(), ProcessorSummary::default()))
+                .collect::<BTreeMap<_, _>>(),
+        };
+        let windowed = filters.from_date.is_some() || filters.to_date.is_some();
+
+        self.for_each(&mut |payment| {
+            let Some(processed_at) = payment.processed_at else {
+                // Pendentes só entram na contagem sem janela
+                if !windowed {
+                    result.count += 1;
+                }
+                return;
+            };
+            if !filters.contains(processed_at) {
+                return;
+            }
+
+            result.count += 1;
+            result.total_amount_cents += payment.amount;
+            result.total_fee_cents += payment.fee;
+            if payment.processor == "failed" {
+                result.count_failed += 1;
+                return;
+            }
+            result.count_processed += 1;
+
+            let summary = result.processors.entry(payment.processor.clone()).or_default();
+            summary.total_requests += 1;
+            summary.total_amount_cents += payment.amount;
+            summary.total_fee_cents += payment.fee;
+        });
+
+        result
+    }
+}
+
+// STORE_BACKEND=memory (padrão) ou file, com STORE_PATH
+pub fn open_store(config: &Config) -> io::Result<Arc<dyn PaymentStore>> {
+    match config.store_backend.as_str() {
+        "memory" => Ok(Arc::new(MemoryPaymentStore::new())),
+        "file" => Ok(Arc::new(FilePaymentStore::open(&config.store_path, config.store_compact_bytes)?)),
+        other => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown store backend '{}' (expected memory or file)", other),
+        )),
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryPaymentStore {
+    payments: DashMap<String, Payment>,
+}
+
+impl MemoryPaymentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PaymentStore for MemoryPaymentStore {
+    fn insert(&self, payment: Payment) -> Result<(), Payment> {
+        match self.payments.entry(payment.id.clone()) {
+            Entry::Occupied(existing) => Err(existing.get().clone()),
+            Entry::Vacant(slot) => {
+                slot.insert(payment);
+                Ok(())
+            }
+        }
+    }
+
+    fn upsert(&self, payment: Payment) {
+        self.payments.insert(payment.id.clone(), payment);
+    }
+
+    fn update_state(&self, payment: Payment) -> bool {
+        match self.payments.get_mut(&payment.id) {
+            Some(mut entry) => {
+                *entry = payment;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove(&self, id: &str) -> Option<Payment> {
+        self.payments.remove(id).map(|(_, payment)| payment)
+    }
+
+    fn get(&self, id: &str) -> Option<Payment> {
+        self.payments.get(id).map(|entry| entry.clone())
+    }
+
+    fn len(&self) -> usize {
+        self.payments.len()
+    }
+
+    fn clear(&self) {
+        self.payments.clear();
+    }
+
+    fn for_each(&self, visit: &mut dyn FnMut(&Payment)) {
+        for entry in self.payments.iter() {
+            visit(entry.value());
+        }
+    }
+}
+
+// Uma linha JSON por mutação; o arquivo é compactado na abertura e, pelo
+// writer, sempre que passa do limite de tamanho
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StoreRecord {
+    Put { payment: Payment },
+    Remove { id: String },
+    Clear,
+}
+
+enum StoreCommand {
+    Append(StoreRecord),
+    Flush(oneshot::Sender<io::Result<()>>),
+}
+
+/// Store embutido em arquivo: índice em memória para leitura e log
+/// append-only como fonte da verdade. O I/O fica numa thread própria, como no
+/// journal; as mutações só seguram o lock do canal para que a ordem no
+/// arquivo seja a mesma do índice. A compactação também roda nessa thread,
+/// a partir do próprio índice.
+pub struct FilePaymentStore {
+    path: PathBuf,
+    index: Arc<MemoryPaymentStore>,
+    log: Mutex<mpsc::UnboundedSender<StoreCommand>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl FilePaymentStore {
+    pub fn open(path: impl AsRef<Path>, compact_bytes: u64) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let index = Arc::new(MemoryPaymentStore::new());
+        record_log::read_records(&path, "store", |record| match record {
+            StoreRecord::Put { payment } => index.upsert(payment),
+            StoreRecord::Remove { id } => {
+                index.remove(&id);
+            }
+            StoreRecord::Clear => index.clear(),
+        })?;
+        let bytes = compact(&path, &index)?;
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let writer = std::thread::Builder::new()
+            .name("payment-store".to_string())
+            .spawn({
+                let path = path.clone();
+                let index = index.clone();
+                let writer = CountingWriter::new(file, bytes);
+                move || run_writer(path, writer, receiver, index, compact_bytes)
+            })?;
+
+        info!("Payment store opened at {} ({} payments)", path.display(), index.len());
+
+        Ok(Self { path, index, log: Mutex::new(sender), writer: Some(writer) })
+    }
+
+    // Chamado com o lock do canal, logo depois de aplicar a mutação ao índice
+    fn append(&self, log: &mpsc::UnboundedSender<StoreCommand>, record: StoreRecord) {
+        if log.send(StoreCommand::Append(record)).is_err() {
+            error!("Payment store writer for {} is gone, record dropped", self.path.display());
+        }
+    }
+}
+
+// Fecha o canal e espera a thread gravar o que ficou nele (e terminar uma
+// compactação em andamento), para que reabrir o arquivo em seguida seja seguro
+impl Drop for FilePaymentStore {
+    fn drop(&mut self) {
+        let (closed, _) = mpsc::unbounded_channel();
+        drop(std::mem::replace(self.log.get_mut().unwrap(), closed));
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl PaymentStore for FilePaymentStore {
+    fn insert(&self, payment: Payment) -> Result<(), Payment> {
+        let log = self.log.lock().unwrap();
+        self.index.insert(payment.clone())?;
+        self.append(&log, StoreRecord::Put { payment });
+        Ok(())
+    }
+
+    fn upsert(&self, payment: Payment) {
+        let log = self.log.lock().unwrap();
+        self.index.upsert(payment.clone());
+        self.append(&log, StoreRecord::Put { payment });
+    }
+
+    fn update_state(&self, payment: Payment) -> bool {
+        let log = self.log.lock().unwrap();
+        if !self.index.update_state(payment.clone()) {
+            return false;
+        }
+        self.append(&log, StoreRecord::Put { payment });
+        true
+    }
+
+    fn remove(&self, id: &str) -> Option<Payment> {
+        let log = self.log.lock().unwrap();
+        let removed = self.index.remove(id)?;
+        self.append(&log, StoreRecord::Remove { id: id.to_string() });
+        Some(removed)
+    }
+
+    fn get(&self, id: &str) -> Option<Payment> {
+        self.index.get(id)
+    }
+
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    fn clear(&self) {
+        let log = self.log.lock().unwrap();
+        self.index.clear();
+        self.append(&log, StoreRecord::Clear);
+    }
+
+    fn for_each(&self, visit: &mut dyn FnMut(&Payment)) {
+        self.index.for_each(visit)
+    }
+
+    // Bloqueia até o writer gravar e fazer fsync de tudo que veio antes
+    fn flush(&self) -> io::Result<()> {
+        let (ack, done) = oneshot::channel();
+        self.log.lock().unwrap()
+            .send(StoreCommand::Flush(ack))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "payment store writer is gone"))?;
+        done.blocking_recv()
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "payment store writer is gone"))?
+    }
+}
+
+// Depois de compactar, só compacta de novo quando o log dobrar (ou no mínimo configurado)
+fn next_compaction(bytes: u64, compact_bytes: u64) -> u64 {
+    bytes.saturating_mul(2).max(compact_bytes)
+}
+
+// Compacta depois de um lote quando o log passa do limite. O snapshot vem do
+// índice, que já contém tudo que foi gravado até aqui; mutações que ainda estão
+// no canal são reaplicadas por cima depois, o que não muda o resultado
+fn run_writer(
+    path: PathBuf,
+    mut writer: CountingWriter,
+    mut receiver: mpsc::UnboundedReceiver<StoreCommand>,
+    index: Arc<MemoryPaymentStore>,
+    compact_bytes: u64,
+) {
+    let mut compact_at = next_compaction(writer.written(), compact_bytes);
+
+    while let Some(first) = receiver.blocking_recv() {
+        let mut batch = vec![first];
+        while let Ok(command) = receiver.try_recv() {
+            batch.push(command);
+        }
+
+        for command in batch {
+            match command {
+                StoreCommand::Append(record) => {
+                    if let Err(e) = write_record(&mut writer, &record) {
+                        error!("Failed to write payment store {}: {}", path.display(), e);
+                    }
+                }
+                StoreCommand::Flush(ack) => {
+                    let _ = ack.send(writer.sync());
+                }
+            }
+        }
+
+        if let Err(e) = writer.flush() {
+            error!("Failed to write payment store {}: {}", path.display(), e);
+        }
+
+        if writer.written() >= compact_at {
+            let result = compact(&path, &index).and_then(|bytes| {
+                let file = OpenOptions::new().append(true).open(&path)?;
+                Ok(CountingWriter::new(file, bytes))
+            });
+            match result {
+                Ok(compacted) => {
+                    writer = compacted;
+                    info!("Payment store {} compacted to {} bytes ({} payments)",
+                          path.display(), writer.written(), index.len());
+                }
+                // Continua no log antigo; tenta de novo quando dobrar
+                Err(e) => error!("Failed to compact payment store {}: {}", path.display(), e),
+            }
+            compact_at = next_compaction(writer.written(), compact_bytes);
+        }
+    }
+}
+
+// Reescreve o log só com o estado atual do índice; devolve o tamanho do arquivo novo
+fn compact(path: &Path, index: &MemoryPaymentStore) -> io::Result<u64> {
+    let mut payments = Vec::with_capacity(index.len());
+    index.for_each(&mut |payment| payments.push(StoreRecord::Put { payment: payment.clone() }));
+    record_log::rewrite(path, payments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn payment(id: &str, processor: &str, processed_at: Option<SystemTime>) -> Payment {
+        Payment {
+            id: id.to_string(),
+            amount: 1_990,
+            processor: processor.to_string(),
+            fee: if processor == "default" { 100 } else { 0 },
+            processed_at,
+        }
+    }
+
+    #[test]
+    fn test_range_returns_finished_payments_in_window() {
+        let store = MemoryPaymentStore::new();
+        let now = SystemTime::now();
+        store.upsert(payment("pending", "pending", None));
+        store.upsert(payment("inside", "default", Some(now)));
+        store.upsert(payment("failed", "failed", Some(now)));
+        store.upsert(payment("before", "fallback", Some(now - Duration::from_secs(60))));
+
+        let window = SummaryFilters {
+            from_date: Some(now - Duration::from_secs(1)),
+            to_date: Some(now + Duration::from_secs(1)),
+        };
+        let mut ids: Vec<String> = store.range(window).into_iter().map(|p| p.id).collect();
+        ids.sort();
+        assert_eq!(ids, ["failed", "inside"]);
+
+        // Sem janela: todos os finalizados, nunca os pendentes
+        assert_eq!(store.range(SummaryFilters::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("payment-store-test-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let now = SystemTime::now();
+
+        {
+            let store = FilePaymentStore::open(&path, 1 << 20).unwrap();
+            assert!(store.insert(payment("a", "pending", None)).is_ok());
+            assert!(store.insert(payment("a", "pending", None)).is_err());
+            assert!(store.insert(payment("b", "pending", None)).is_ok());
+            assert!(store.update_state(payment("a", "default", Some(now))));
+            assert!(!store.update_state(payment("ghost", "default", Some(now))));
+            store.remove("b");
+            store.flush().unwrap();
+        }
+
+        let store = FilePaymentStore::open(&path, 1 << 20).unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get("a").unwrap().processor, "default");
+
+        let window = SummaryFilters {
+            from_date: Some(now - Duration::from_secs(1)),
+            to_date: Some(now + Duration::from_secs(1)),
+        };
+        assert_eq!(store.range(window).len(), 1);
+
+        let summary = store.aggregate(window, &["default".to_string(), "fallback".to_string()]);
+        assert_eq!(summary.processors["default"].total_requests, 1);
+        assert_eq!(summary.processors["default"].total_fee_cents, 100);
+        assert_eq!(summary.processors["fallback"].total_requests, 0);
+
+        store.clear();
+        store.flush().unwrap();
+        drop(store);
+        assert!(FilePaymentStore::open(&path, 1 << 20).unwrap().is_empty());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_file_store_compacts_past_threshold() {
+        let path = std::env::temp_dir().join(format!("payment-store-compact-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let now = SystemTime::now();
+
+        let store = FilePaymentStore::open(&path, 4_096).unwrap();
+        for round in 0..200 {
+            store.upsert(payment("a", "pending", None));
+            store.update_state(payment("a", if round % 2 == 0 { "default" } else { "fallback" }, Some(now)));
+            store.flush().unwrap();
+        }
+
+        // 400 Puts de ~100 bytes sem compactação passariam de 40KB
+        assert!(fs::metadata(&path).unwrap().len() < 3 * 4_096);
+        drop(store);
+
+        let store = FilePaymentStore::open(&path, 4_096).unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get("a").unwrap().processor, "fallback");
+        let _ = fs::remove_file(&path);
+    }
+}
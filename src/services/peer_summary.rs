@@ -0,0 +1,180 @@
+use crate::app::config::Config;
+use crate::models::payment::Payment;
+use crate::services::payment_service::{ProcessorSummary, SummaryFilters};
+use crate::utils::time::format_timestamp;
+use futures::future::join_all;
+use reqwest::Client;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, UNIX_EPOCH};
+
+pub type ProcessorTotals = BTreeMap<String, ProcessorSummary>;
+
+// Rota que devolve só os pagamentos processados localmente; os peers nunca
+// repassam a consulta adiante. Só existe no listener TCP, que o load balancer não usa
+pub const LOCAL_PAYMENTS_PATH: &str = "/internal/payments";
+
+/// Totais do cluster e os peers que ficaram de fora deles.
+pub struct ClusterSummary {
+    pub totals: ProcessorTotals,
+    pub unreachable_peers: Vec<String>,
+}
+
+impl ClusterSummary {
+    pub fn is_partial(&self) -> bool {
+        !self.unreachable_peers.is_empty()
+    }
+}
+
+/// Consulta os pagamentos das outras réplicas para montar o summary do cluster.
+pub struct PeerSummaryClient {
+    client: Client,
+    peers: Vec<String>,
+    require_all_peers: bool,
+}
+
+impl PeerSummaryClient {
+    pub fn from_config(config: &Config) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.peer_timeout_ms))
+            .build()
+            .expect("Failed to create peer HTTP client");
+
+        Self {
+            client,
+            peers: config.peer_urls.clone(),
+            require_all_peers: config.summary_require_all_peers,
+        }
+    }
+
+    pub fn has_peers(&self) -> bool {
+        !self.peers.is_empty()
+    }
+
+    pub fn require_all_peers(&self) -> bool {
+        self.require_all_peers
+    }
+
+    pub async fn fetch(&self, filters: SummaryFilters) -> Vec<(String, Result<Vec<Payment>, String>)> {
+        let mut query = Vec::new();
+        if let Some(from) = filters.from_date {
+            query.push(("from", format_timestamp(from)));
+        }
+        if let Some(to) = filters.to_date {
+            query.push(("to", format_timestamp(to)));
+        }
+
+        let requests = self.peers.iter().map(|peer| {
+            let query = &query;
+            async move {
+                let result = self.fetch_one(peer, query).await;
+                (peer.clone(), result)
+            }
+        });
+
+        join_all(requests).await
+    }
+
+    async fn fetch_one(&self, peer: &str, query: &[(&str, String)]) -> Result<Vec<Payment>, String> {
+        let response = self.client
+            .get(format!("{}{}", peer, LOCAL_PAYMENTS_PATH))
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+
+        response.json::<Vec<Payment>>()
+            .await
+            .map_err(|e| format!("invalid payments payload: {}", e))
+    }
+}
+
+// Totais do cluster a partir dos pagamentos de cada réplica. O dedup do
+// submit é por réplica, então o mesmo correlationId pode ter sido aceito
+// pelas duas: conta uma vez só, ficando com o processamento mais antigo.
+// Soma em centavos inteiros: o resultado não depende da ordem das réplicas.
+// Todo processor em `processors` aparece, mesmo sem pagamentos
+pub fn merge_payments(processors: &[String], replicas: impl IntoIterator<Item = Vec<Payment>>) -> ProcessorTotals {
+    let mut unique: HashMap<String, Payment> = HashMap::new();
+    for payment in replicas.into_iter().flatten() {
+        match unique.entry(payment.id.clone()) {
+            Entry::Occupied(mut existing) if merge_key(&payment) < merge_key(existing.get()) => {
+                existing.insert(payment);
+            }
+            Entry::Occupied(_) => {}
+            Entry::Vacant(slot) => {
+                slot.insert(payment);
+            }
+        }
+    }
+
+    let mut totals: ProcessorTotals = processors.iter()
+        .map(|name| (name.clone(), ProcessorSummary::default()))
+        .collect();
+    for payment in unique.into_values() {
+        let total = totals.entry(payment.processor).or_default();
+        total.total_requests += 1;
+        total.total_amount_cents += payment.amount;
+        total.total_fee_cents += payment.fee;
+    }
+    totals
+}
+
+// Milissegundos, a precisão que atravessa o JSON: as duas réplicas escolhem o mesmo
+fn merge_key(payment: &Payment) -> (u128, &str) {
+    let millis = payment.processed_at
+        .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis());
+    (millis, payment.processor.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payment(id: &str, processor: &str, amount: u64, at_ms: u64) -> Payment {
+        Payment {
+            id: id.to_string(),
+            amount,
+            processor: processor.to_string(),
+            fee: amount / 20,
+            processed_at: Some(UNIX_EPOCH + Duration::from_millis(at_ms)),
+        }
+    }
+
+    #[test]
+    fn test_merge_counts_shared_correlation_id_once() {
+        let processors = vec!["default".to_string(), "fallback".to_string()];
+        let app1 = vec![payment("a", "default", 1_990, 10), payment("b", "default", 3_980, 20)];
+        // "b" também foi aceito pelo app2, depois e por outro processor
+        let app2: Vec<Payment> = serde_json::from_str(&serde_json::to_string(&vec![
+            payment("b", "fallback", 3_980, 25),
+            payment("c", "fallback", 1_000, 30),
+        ]).unwrap()).unwrap();
+
+        let seen_from_app1 = merge_payments(&processors, [app1.clone(), app2.clone()]);
+        let seen_from_app2 = merge_payments(&processors, [app2, app1]);
+
+        assert_eq!(
+            serde_json::to_string(&seen_from_app1).unwrap(),
+            serde_json::to_string(&seen_from_app2).unwrap()
+        );
+        assert_eq!(seen_from_app1["default"].total_requests, 2);
+        assert_eq!(seen_from_app1["default"].total_amount_cents, 5_970);
+        assert_eq!(seen_from_app1["fallback"].total_requests, 1);
+        assert_eq!(seen_from_app1["fallback"].total_amount_cents, 1_000);
+    }
+
+    #[test]
+    fn test_merge_lists_processors_without_payments() {
+        let processors = vec!["default".to_string(), "fallback".to_string()];
+        let totals = merge_payments(&processors, [Vec::new()]);
+
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals["fallback"].total_requests, 0);
+    }
+}
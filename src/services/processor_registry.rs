@@ -0,0 +1,57 @@
+use crate::app::config::{Config, ProcessorConfig};
+use crate::services::concurrency_limiter::{AdaptiveLimiter, ConcurrencyLimitConfig};
+use crate::services::payment_processor_client::ProcessorErrorStats;
+use std::time::Duration;
+
+pub struct RegisteredProcessor {
+    pub config: ProcessorConfig,
+    pub errors: ProcessorErrorStats,
+    pub limiter: AdaptiveLimiter,
+}
+
+impl RegisteredProcessor {
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.config.timeout_ms)
+    }
+}
+
+/// Processors configurados, em ordem de prioridade (o primeiro é o preferido).
+pub struct ProcessorRegistry {
+    processors: Vec<RegisteredProcessor>,
+}
+
+impl ProcessorRegistry {
+    pub fn from_config(config: &Config) -> Self {
+        let limits = ConcurrencyLimitConfig::from_config(config);
+        let mut processors: Vec<RegisteredProcessor> = config.processors.iter()
+            .map(|processor| RegisteredProcessor {
+                config: processor.clone(),
+                errors: ProcessorErrorStats::default(),
+                limiter: AdaptiveLimiter::new(limits.clone()),
+            })
+            .collect();
+        processors.sort_by_key(|p| p.config.priority);
+
+        Self { processors }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegisteredProcessor> {
+        self.processors.iter().find(|p| p.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredProcessor> {
+        self.processors.iter()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.processors.iter().map(|p| p.name().to_string()).collect()
+    }
+}
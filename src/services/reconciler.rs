@@ -0,0 +1,110 @@
+use crate::services::payment_service::ProcessorSummary;
+use crate::utils::time::iso_timestamp_opt;
+use serde::{Serialize, Serializer};
+use std::time::SystemTime;
+
+// Resultado da comparação entre nossos totais e o /admin/payments-summary de um processor
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessorReconciliation {
+    pub processor: String,
+    pub local: ProcessorSummary,
+    pub remote: Option<ProcessorSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(rename = "countDrift")]
+    pub count_drift: i64,
+    #[serde(rename = "amountDrift", serialize_with = "serialize_signed_cents")]
+    pub amount_drift_cents: i64,
+    // Processados por esse processor segundo nós, mas desconhecidos por ele
+    #[serde(rename = "missingOnProcessor", skip_serializing_if = "Option::is_none")]
+    pub missing_on_processor: Option<Vec<String>>,
+    // Aceitos pelo processor, mas marcados como falha/pendentes aqui
+    #[serde(rename = "missingLocally", skip_serializing_if = "Option::is_none")]
+    pub missing_locally: Option<Vec<String>>,
+}
+
+impl ProcessorReconciliation {
+    pub fn new(processor: &str, local: ProcessorSummary, remote: Result<ProcessorSummary, String>) -> Self {
+        let (remote, error) = match remote {
+            Ok(remote) => (Some(remote), None),
+            Err(e) => (None, Some(e)),
+        };
+
+        // Drift = processor - local; positivo significa que o processor registrou mais
+        let (count_drift, amount_drift_cents) = match &remote {
+            Some(remote) => (
+                remote.total_requests as i64 - local.total_requests as i64,
+                remote.total_amount_cents as i64 - local.total_amount_cents as i64,
+            ),
+            None => (0, 0),
+        };
+
+        Self {
+            processor: processor.to_string(),
+            local,
+            remote,
+            error,
+            count_drift,
+            amount_drift_cents,
+            missing_on_processor: None,
+            missing_locally: None,
+        }
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.error.is_none()
+            && self.count_drift == 0
+            && self.amount_drift_cents == 0
+            && self.missing_on_processor.as_ref().is_none_or(|ids| ids.is_empty())
+            && self.missing_locally.as_ref().is_none_or(|ids| ids.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationReport {
+    #[serde(with = "iso_timestamp_opt")]
+    pub from: Option<SystemTime>,
+    #[serde(with = "iso_timestamp_opt")]
+    pub to: Option<SystemTime>,
+    pub consistent: bool,
+    pub processors: Vec<ProcessorReconciliation>,
+}
+
+impl ReconciliationReport {
+    pub fn new(from: Option<SystemTime>, to: Option<SystemTime>, processors: Vec<ProcessorReconciliation>) -> Self {
+        Self {
+            from,
+            to,
+            consistent: processors.iter().all(ProcessorReconciliation::is_consistent),
+            processors,
+        }
+    }
+}
+
+fn serialize_signed_cents<S: Serializer>(cents: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(*cents as f64 / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(total_requests: u64, total_amount_cents: u64) -> ProcessorSummary {
+        ProcessorSummary { total_requests, total_amount_cents, total_fee_cents: 0 }
+    }
+
+    #[test]
+    fn test_drift_is_remote_minus_local() {
+        let reconciliation = ProcessorReconciliation::new("default", summary(10, 19_900), Ok(summary(11, 21_890)));
+        assert_eq!(reconciliation.count_drift, 1);
+        assert_eq!(reconciliation.amount_drift_cents, 1_990);
+        assert!(!reconciliation.is_consistent());
+
+        let matching = ProcessorReconciliation::new("fallback", summary(3, 500), Ok(summary(3, 500)));
+        assert!(matching.is_consistent());
+
+        let unreachable = ProcessorReconciliation::new("fallback", summary(3, 500), Err("timeout".to_string()));
+        assert_eq!(unreachable.count_drift, 0);
+        assert!(!unreachable.is_consistent());
+    }
+}
@@ -0,0 +1,96 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use tracing::warn;
+
+// Log de registros JSON, um por linha, usado pelo journal e pelo store em disco
+
+// Aplica cada registro do arquivo na ordem; arquivo ausente é um log vazio.
+// `kind` só aparece no aviso de linha corrompida
+pub fn read_records<T: DeserializeOwned>(
+    path: &Path,
+    kind: &str,
+    mut apply: impl FnMut(T),
+) -> io::Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for (line_number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(&line) {
+            Ok(record) => apply(record),
+            // Provavelmente uma escrita interrompida pelo crash
+            Err(e) => warn!("Ignoring corrupted {} line {}: {}", kind, line_number + 1, e),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_record<T: Serialize>(writer: &mut impl Write, record: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writer.write_all(b"\n")
+}
+
+// Substitui o log pelos registros dados: grava num temporário, faz fsync e
+// renomeia. Devolve o tamanho do arquivo novo
+pub fn rewrite<T: Serialize>(path: &Path, records: impl IntoIterator<Item = T>) -> io::Result<u64> {
+    let tmp_path = path.with_extension("compacting");
+    let bytes = {
+        let mut writer = CountingWriter::new(File::create(&tmp_path)?, 0);
+        for record in records {
+            write_record(&mut writer, &record)?;
+        }
+        writer.sync()?;
+        writer.written()
+    };
+
+    fs::rename(&tmp_path, path)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(bytes)
+}
+
+/// Writer bufferizado que conta os bytes do arquivo, para decidir quando compactar.
+pub struct CountingWriter {
+    inner: BufWriter<File>,
+    written: u64,
+}
+
+impl CountingWriter {
+    pub fn new(file: File, written: u64) -> Self {
+        Self { inner: BufWriter::new(file), written }
+    }
+
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    // Esvazia o buffer e faz fsync dos dados
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.inner.get_ref().sync_data()
+    }
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,175 @@
+use crate::app::config::Config;
+use crate::queue::QueuedPayment;
+use crate::services::atomic_metrics::AtomicMetrics;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub max_age: Duration,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_attempts: config.retry_max_attempts,
+            max_age: Duration::from_millis(config.retry_max_age_ms),
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+        }
+    }
+
+    // Backoff exponencial com jitter: sorteia entre metade e o teto da tentativa
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let ceiling = self.base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        let half = ceiling / 2;
+        let jitter_ms = fastrand::u64(0..=(ceiling - half).as_millis() as u64);
+
+        half + Duration::from_millis(jitter_ms)
+    }
+}
+
+struct RetryState {
+    attempts: u32,
+    first_failure_at: Instant,
+}
+
+#[derive(Debug)]
+pub enum RetryDecision {
+    Scheduled { attempt: u32, delay: Duration },
+    Exhausted { attempts: u32 },
+}
+
+pub struct RetryScheduler {
+    policy: RetryPolicy,
+    payment_sender: mpsc::Sender<QueuedPayment>,
+    states: Arc<DashMap<String, RetryState>>,
+    metrics: Arc<AtomicMetrics>,
+}
+
+impl RetryScheduler {
+    pub fn new(
+        policy: RetryPolicy,
+        payment_sender: mpsc::Sender<QueuedPayment>,
+        metrics: Arc<AtomicMetrics>,
+    ) -> Self {
+        Self {
+            policy,
+            payment_sender,
+            states: Arc::new(DashMap::new()),
+            metrics,
+        }
+    }
+
+    // Registra a falha e, se ainda houver orçamento, reenfileira após o backoff.
+    // A fila só fecha no shutdown: aí o pagamento continua pendente no journal
+    // e volta para a fila no restore
+    pub fn schedule(&self, queued: QueuedPayment) -> RetryDecision {
+        let attempt = {
+            let mut state = self.states.entry(queued.request.id.clone()).or_insert_with(|| RetryState {
+                attempts: 0,
+                first_failure_at: Instant::now(),
+            });
+            state.attempts += 1;
+
+            if state.attempts >= self.policy.max_attempts
+                || state.first_failure_at.elapsed() >= self.policy.max_age
+            {
+                let attempts = state.attempts;
+                drop(state);
+                self.states.remove(&queued.request.id);
+                self.metrics.increment_retries_exhausted();
+                return RetryDecision::Exhausted { attempts };
+            }
+
+            state.attempts
+        };
+
+        let delay = self.policy.delay_for(attempt);
+        self.metrics.increment_retries_scheduled();
+
+        let sender = self.payment_sender.clone();
+        let states = self.states.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Err(mpsc::error::SendError(queued)) = sender.send(queued).await {
+                warn!("Queue closed before retrying payment {}; left pending for replay", queued.request.id);
+                states.remove(&queued.request.id);
+            }
+        });
+
+        RetryDecision::Scheduled { attempt, delay }
+    }
+
+    pub fn clear(&self, id: &str) {
+        self.states.remove(id);
+    }
+
+    pub fn clear_all(&self) {
+        self.states.clear();
+    }
+
+    pub fn pending(&self) -> u64 {
+        self.states.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::payment::PaymentRequest;
+
+    #[test]
+    fn test_delay_for_stays_within_bounds() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            max_age: Duration::from_secs(60),
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+
+        for _ in 0..100 {
+            let first = policy.delay_for(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+            let third = policy.delay_for(3);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+
+            let capped = policy.delay_for(10);
+            assert!(capped >= Duration::from_millis(500) && capped <= Duration::from_millis(1000));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_closed_queue_clears_retry_state() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            max_age: Duration::from_secs(60),
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        let (sender, receiver) = mpsc::channel(1);
+        drop(receiver);
+        let scheduler = RetryScheduler::new(policy, sender, Arc::new(AtomicMetrics::new()));
+
+        let queued = QueuedPayment {
+            request: PaymentRequest { id: "a".to_string(), amount: 100 },
+            generation: 0,
+        };
+        let decision = scheduler.schedule(queued);
+        assert!(matches!(decision, RetryDecision::Scheduled { attempt: 1, .. }));
+        assert_eq!(scheduler.pending(), 1);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(scheduler.pending(), 0);
+    }
+}
@@ -1,3 +1,7 @@
+use crate::services::circuit_breaker::{
+    CircuitBreaker, CircuitBreakerConfig, CircuitBreakerSnapshot, CircuitBreakerState,
+    SlidingWindowBreaker,
+};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -13,13 +17,6 @@ pub struct ProcessorStats {
     pub circuit_breaker_state: CircuitBreakerState,
 }
 
-#[derive(Debug, Clone)]
-pub enum CircuitBreakerState {
-    Closed,
-    Open,
-    HalfOpen,
-}
-
 impl ProcessorStats {
     pub fn new() -> Self {
         Self {
@@ -32,131 +29,180 @@ impl ProcessorStats {
         }
     }
 
-    pub fn success_rate(&self) -> f64 {
-        let total = self.success_count + self.failure_count;
-        if total == 0 {
-            return 1.0;
-        }
-        self.success_count as f64 / total as f64
-    }
-
+    // A taxa de falha recente já é avaliada pelo breaker
+    #[cfg(test)]
     pub fn is_healthy(&self) -> bool {
-        match self.circuit_breaker_state {
-            CircuitBreakerState::Closed => self.success_rate() > 0.5,
-            CircuitBreakerState::Open => false,
-            CircuitBreakerState::HalfOpen => true,
-        }
+        self.circuit_breaker_state != CircuitBreakerState::Open
     }
 }
 
+struct ProcessorEntry {
+    stats: ProcessorStats,
+    breaker: Box<dyn CircuitBreaker>,
+}
+
 pub struct SmartFallbackManager {
-    processor_stats: Arc<RwLock<HashMap<String, ProcessorStats>>>,
-    circuit_breaker_threshold: u64,
-    circuit_breaker_timeout: Duration,
+    processor_stats: Arc<RwLock<HashMap<String, ProcessorEntry>>>,
+    // Config do breaker por processor; `default_breaker_config` para os demais
+    breaker_configs: HashMap<String, CircuitBreakerConfig>,
+    default_breaker_config: CircuitBreakerConfig,
 }
 
 impl SmartFallbackManager {
-    pub fn new(circuit_breaker_threshold: u64, circuit_breaker_timeout: Duration) -> Self {
+    pub fn new(
+        default_breaker_config: CircuitBreakerConfig,
+        breaker_configs: HashMap<String, CircuitBreakerConfig>,
+    ) -> Self {
         Self {
             processor_stats: Arc::new(RwLock::new(HashMap::new())),
-            circuit_breaker_threshold,
-            circuit_breaker_timeout,
+            breaker_configs,
+            default_breaker_config,
         }
     }
 
+    fn entry<'a>(
+        &self,
+        stats_map: &'a mut HashMap<String, ProcessorEntry>,
+        processor_id: &str,
+    ) -> &'a mut ProcessorEntry {
+        stats_map.entry(processor_id.to_string())
+            .or_insert_with(|| {
+                let config = self.breaker_configs.get(processor_id)
+                    .unwrap_or(&self.default_breaker_config);
+                ProcessorEntry {
+                    stats: ProcessorStats::new(),
+                    breaker: Box::new(SlidingWindowBreaker::new(processor_id, config.clone())),
+                }
+            })
+    }
+
+    // Admite a chamada no breaker do processor; toda chamada admitida deve
+    // terminar em record_success, record_failure ou record_ignored
+    pub async fn try_acquire(&self, processor_id: &str) -> bool {
+        let mut stats_map = self.processor_stats.write().await;
+        self.entry(&mut stats_map, processor_id).breaker.can_execute()
+    }
+
+    pub async fn record_ignored(&self, processor_id: &str) {
+        let mut stats_map = self.processor_stats.write().await;
+        self.entry(&mut stats_map, processor_id).breaker.record_ignored();
+    }
+
     pub async fn record_success(&self, processor_id: &str, latency: Duration) {
         let mut stats_map = self.processor_stats.write().await;
-        let stats = stats_map.entry(processor_id.to_string())
-            .or_insert_with(ProcessorStats::new);
+        let entry = self.entry(&mut stats_map, processor_id);
+        let stats = &mut entry.stats;
 
         stats.success_count += 1;
         stats.last_success = Some(Instant::now());
-        
-        let new_latency_ms = ((stats.latency_avg.as_millis() * 9 + latency.as_millis()) / 10) as u64;
-        stats.latency_avg = Duration::from_millis(new_latency_ms);
 
-        self.update_circuit_breaker(stats);
+        // Primeira amostra define a média; depois EWMA com peso 0.1
+        stats.latency_avg = if stats.success_count == 1 {
+            latency
+        } else {
+            let new_latency_ms = ((stats.latency_avg.as_millis() * 9 + latency.as_millis()) / 10) as u64;
+            Duration::from_millis(new_latency_ms)
+        };
+
+        entry.breaker.record_success();
     }
 
     pub async fn record_failure(&self, processor_id: &str) {
         let mut stats_map = self.processor_stats.write().await;
-        let stats = stats_map.entry(processor_id.to_string())
-            .or_insert_with(ProcessorStats::new);
+        let entry = self.entry(&mut stats_map, processor_id);
 
-        stats.failure_count += 1;
-        stats.last_failure = Some(Instant::now());
+        entry.stats.failure_count += 1;
+        entry.stats.last_failure = Some(Instant::now());
 
-        self.update_circuit_breaker(stats);
+        entry.breaker.record_failure();
     }
 
-    fn update_circuit_breaker(&self, stats: &mut ProcessorStats) {
-        match stats.circuit_breaker_state {
-            CircuitBreakerState::Closed => {
-                if stats.failure_count >= self.circuit_breaker_threshold {
-                    stats.circuit_breaker_state = CircuitBreakerState::Open;
-                }
-            }
-            CircuitBreakerState::Open => {
-                if let Some(last_failure) = stats.last_failure {
-                    if last_failure.elapsed() >= self.circuit_breaker_timeout {
-                        stats.circuit_breaker_state = CircuitBreakerState::HalfOpen;
-                    }
-                }
-            }
-            CircuitBreakerState::HalfOpen => {
-                if stats.success_count > 0 {
-                    stats.circuit_breaker_state = CircuitBreakerState::Closed;
-                    stats.failure_count = 0;
-                }
-            }
-        }
+    pub async fn get_processor_stats(&self, processor_id: &str) -> Option<ProcessorStats> {
+        let stats_map = self.processor_stats.read().await;
+        stats_map.get(processor_id).map(|entry| ProcessorStats {
+            circuit_breaker_state: entry.breaker.state(),
+            ..entry.stats.clone()
+        })
     }
 
-    pub async fn get_best_processor(&self, available_processors: &[String]) -> Option<String> {
+    pub async fn is_processor_available(&self, processor_id: &str) -> bool {
         let stats_map = self.processor_stats.read().await;
         
-        let mut best_processor: Option<String> = None;
-        let mut best_score = 0.0f64;
-
-        for processor_id in available_processors {
-            if let Some(stats) = stats_map.get(processor_id) {
-                if !stats.is_healthy() {
-                    continue;
-                }
-
-                let score = self.calculate_processor_score(stats);
-                if score > best_score {
-                    best_score = score;
-                    best_processor = Some(processor_id.clone());
-                }
-            } else {
-                if best_processor.is_none() {
-                    best_processor = Some(processor_id.clone());
-                }
-            }
+        match stats_map.get(processor_id) {
+            Some(entry) => entry.breaker.allows_requests(),
+            None => true,
         }
+    }
 
-        best_processor
+    pub async fn breaker_snapshot(&self, processor_id: &str) -> Option<CircuitBreakerSnapshot> {
+        let mut stats_map = self.processor_stats.write().await;
+        stats_map.get_mut(processor_id).map(|entry| entry.breaker.snapshot())
     }
 
-    fn calculate_processor_score(&self, stats: &ProcessorStats) -> f64 {
-        let success_rate = stats.success_rate();
-        let latency_score = 1.0 / (1.0 + stats.latency_avg.as_millis() as f64 / 100.0);
-        
-        success_rate * 0.7 + latency_score * 0.3
+    pub async fn reset(&self) {
+        let mut stats_map = self.processor_stats.write().await;
+        for entry in stats_map.values_mut() {
+            entry.stats = ProcessorStats::new();
+            entry.breaker.reset();
+        }
     }
+}
 
-    pub async fn get_processor_stats(&self, processor_id: &str) -> Option<ProcessorStats> {
-        let stats_map = self.processor_stats.read().await;
-        stats_map.get(processor_id).cloned()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_processor_recovers_through_half_open() {
+        let manager = SmartFallbackManager::new(CircuitBreakerConfig {
+            failure_rate_threshold: 0.5,
+            window_size: 10,
+            window_duration: None,
+            min_requests: 2,
+            open_timeout: Duration::ZERO,
+            half_open_probes: 1,
+        }, HashMap::new());
+
+        manager.record_failure("default").await;
+        manager.record_failure("default").await;
+        let stats = manager.get_processor_stats("default").await.unwrap();
+        assert_eq!(stats.circuit_breaker_state, CircuitBreakerState::HalfOpen);
+        assert!(manager.is_processor_available("default").await);
+
+        assert!(manager.try_acquire("default").await);
+        assert!(!manager.try_acquire("default").await);
+        manager.record_success("default", Duration::from_millis(10)).await;
+
+        let stats = manager.get_processor_stats("default").await.unwrap();
+        assert_eq!(stats.circuit_breaker_state, CircuitBreakerState::Closed);
+        assert!(stats.is_healthy());
+        assert_eq!(stats.failure_count, 2);
     }
 
-    pub async fn is_processor_available(&self, processor_id: &str) -> bool {
-        let stats_map = self.processor_stats.read().await;
-        
-        match stats_map.get(processor_id) {
-            Some(stats) => stats.is_healthy(),
-            None => true,
-        }
+    #[tokio::test]
+    async fn test_breakers_use_per_processor_config() {
+        let strict = CircuitBreakerConfig {
+            failure_rate_threshold: 0.5,
+            window_size: 10,
+            window_duration: None,
+            min_requests: 2,
+            open_timeout: Duration::from_secs(60),
+            half_open_probes: 1,
+        };
+        // fallback com breaker próprio, mais tolerante
+        let lenient = CircuitBreakerConfig { min_requests: 5, ..strict.clone() };
+        let manager = SmartFallbackManager::new(strict, HashMap::from([("fallback".to_string(), lenient)]));
+
+        assert!(manager.is_processor_available("default").await);
+
+        manager.record_failure("fallback").await;
+        manager.record_failure("fallback").await;
+        manager.record_success("fallback", Duration::from_millis(50)).await;
+        manager.record_failure("default").await;
+        manager.record_failure("default").await;
+
+        assert!(!manager.try_acquire("default").await);
+        assert!(!manager.is_processor_available("default").await);
+        assert!(manager.is_processor_available("fallback").await);
     }
-}
\ No newline at end of file
+}
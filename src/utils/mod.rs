@@ -0,0 +1,2 @@
+pub mod money;
+pub mod time;
\ No newline at end of file
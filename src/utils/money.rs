@@ -1,5 +1,9 @@
 // Utilitários para manipulação de valores monetários
 
+use serde::Serialize;
+
+// Taxa simples truncada; o cálculo de verdade fica no FeeSchedule
+#[cfg(test)]
 pub fn calculate_fee(amount: u64, fee_rate: f64) -> u64 {
     (amount as f64 * fee_rate) as u64
 }
@@ -8,12 +12,126 @@ pub fn format_currency(amount: u64) -> String {
     format!("${:.2}", amount as f64 / 100.0)
 }
 
+#[cfg(test)]
 pub fn parse_currency(currency_str: &str) -> Result<u64, std::num::ParseFloatError> {
-    let cleaned = currency_str.replace('$', "").replace(',', "");
+    let cleaned = currency_str.replace(['$', ','], "");
     let float_value: f64 = cleaned.parse()?;
     Ok((float_value * 100.0) as u64)
 }
 
+// Converte um decimal ("19.90", "20", "0.5") em centavos sem passar por f64.
+// Valores negativos, com mais de duas casas ou em notação científica são rejeitados.
+pub fn parse_decimal_cents(value: &str) -> Option<u64> {
+    let (int_part, frac_part) = match value.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, frac_part),
+        None => (value, ""),
+    };
+
+    if int_part.is_empty() || !int_part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if frac_part.len() > 2 || !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if value.contains('.') && frac_part.is_empty() {
+        return None;
+    }
+
+    let units: u64 = int_part.parse().ok()?;
+    let cents: u64 = format!("{:0<2}", frac_part).parse().ok()?;
+
+    units.checked_mul(100)?.checked_add(cents)
+}
+
+// Para somas em float vindas de fora (ex.: 1234.5600000000002): tenta o parse
+// exato e, se não der, arredonda para o centavo mais próximo
+pub fn round_decimal_cents(number: &serde_json::Number) -> Option<u64> {
+    if let Some(cents) = parse_decimal_cents(&number.to_string()) {
+        return Some(cents);
+    }
+
+    let cents = (number.as_f64()? * 100.0).round();
+    (cents.is_finite() && cents >= 0.0 && cents <= u64::MAX as f64).then_some(cents as u64)
+}
+
+pub fn cents_to_decimal(cents: u64) -> f64 {
+    cents as f64 / 100.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeRounding {
+    HalfUp,
+    Up,
+    Down,
+}
+
+impl FeeRounding {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "half_up" => Some(Self::HalfUp),
+            "up" => Some(Self::Up),
+            "down" => Some(Self::Down),
+            _ => None,
+        }
+    }
+}
+
+// Taxa de um processor: percentual (em basis points, sem erro de f64) + parte fixa
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FeeSchedule {
+    pub rate_bps: u64,
+    pub fixed_cents: u64,
+    pub rounding: FeeRounding,
+}
+
+impl FeeSchedule {
+    pub fn new(rate: f64, fixed_cents: u64, rounding: FeeRounding) -> Self {
+        Self {
+            rate_bps: (rate.max(0.0) * 10_000.0).round() as u64,
+            fixed_cents,
+            rounding,
+        }
+    }
+
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        let numerator = amount as u128 * self.rate_bps as u128;
+        let variable = match self.rounding {
+            FeeRounding::HalfUp => (numerator + 5_000) / 10_000,
+            FeeRounding::Up => numerator.div_ceil(10_000),
+            FeeRounding::Down => numerator / 10_000,
+        };
+
+        (variable as u64).saturating_add(self.fixed_cents)
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate_bps as f64 / 10_000.0
+    }
+}
+
+// (De)serializa centavos internos como o número decimal do contrato (19.90)
+pub mod decimal_cents {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(cents: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(super::cents_to_decimal(*cents))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let number = serde_json::Number::deserialize(deserializer)?;
+        super::parse_decimal_cents(&number.to_string())
+            .ok_or_else(|| D::Error::custom(format!("invalid amount: {}", number)))
+    }
+
+    // Totais somados em float pelo outro lado: arredonda em vez de rejeitar
+    pub fn deserialize_rounded<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let number = serde_json::Number::deserialize(deserializer)?;
+        super::round_decimal_cents(&number)
+            .ok_or_else(|| D::Error::custom(format!("invalid amount: {}", number)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -24,9 +142,50 @@ mod tests {
         assert_eq!(calculate_fee(2000, 0.10), 200); // 10% de 2000 = 200
     }
 
+    #[test]
+    fn test_parse_currency() {
+        assert_eq!(parse_currency("$1,000.50"), Ok(100050));
+        assert!(parse_currency("abc").is_err());
+    }
+
     #[test]
     fn test_format_currency() {
         assert_eq!(format_currency(1000), "$10.00");
         assert_eq!(format_currency(2550), "$25.50");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_decimal_cents() {
+        assert_eq!(parse_decimal_cents("19.90"), Some(1990));
+        assert_eq!(parse_decimal_cents("19.9"), Some(1990));
+        assert_eq!(parse_decimal_cents("20"), Some(2000));
+        assert_eq!(parse_decimal_cents("0.01"), Some(1));
+        assert_eq!(parse_decimal_cents("19.901"), None);
+        assert_eq!(parse_decimal_cents("-1.00"), None);
+        assert_eq!(parse_decimal_cents("1e3"), None);
+        assert_eq!(parse_decimal_cents("1."), None);
+    }
+
+    #[test]
+    fn test_round_decimal_cents() {
+        let number = |raw: &str| serde_json::from_str::<serde_json::Number>(raw).unwrap();
+        assert_eq!(round_decimal_cents(&number("19.90")), Some(1990));
+        assert_eq!(round_decimal_cents(&number("1234.5600000000002")), Some(123_456));
+        assert_eq!(round_decimal_cents(&number("0.125")), Some(13));
+        assert_eq!(round_decimal_cents(&number("1e3")), Some(100_000));
+        assert_eq!(round_decimal_cents(&number("-1.5")), None);
+    }
+
+    #[test]
+    fn test_fee_schedule() {
+        let half_up = FeeSchedule::new(0.05, 0, FeeRounding::HalfUp);
+        assert_eq!(half_up.fee_for(1990), 100); // 99.5 -> 100
+        assert_eq!(half_up.fee_for(1989), 99); // 99.45 -> 99
+
+        let down = FeeSchedule::new(0.05, 0, FeeRounding::Down);
+        assert_eq!(down.fee_for(1990), 99);
+
+        let up_with_fixed = FeeSchedule::new(0.15, 10, FeeRounding::Up);
+        assert_eq!(up_with_fixed.fee_for(1001), 151 + 10); // 150.15 -> 151
+    }
+}
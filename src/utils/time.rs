@@ -0,0 +1,63 @@
+// Utilitários para datas ISO-8601 usadas no contrato
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use std::time::SystemTime;
+
+// Formato usado pelos processors: 2025-07-15T12:34:56.000Z
+pub fn format_timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+// Aceita RFC 3339 com offset; sem offset assume UTC
+pub fn parse_timestamp(raw: &str) -> Option<SystemTime> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(raw) {
+        return Some(date.into());
+    }
+
+    NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|date| date.and_utc().into())
+}
+
+// (De)serializa Option<SystemTime> como string ISO-8601
+pub mod iso_timestamp_opt {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::time::SystemTime;
+
+    pub fn serialize<S: Serializer>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.serialize_str(&super::format_timestamp(*time)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SystemTime>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(raw) => super::parse_timestamp(&raw)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid timestamp: {}", raw))),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = UNIX_EPOCH + Duration::from_millis(1_752_582_896_000);
+        assert_eq!(parse_timestamp("2025-07-15T12:34:56.000Z"), Some(expected));
+        assert_eq!(parse_timestamp("2025-07-15T09:34:56-03:00"), Some(expected));
+        assert_eq!(parse_timestamp("2025-07-15T12:34:56"), Some(expected));
+        assert_eq!(parse_timestamp("15/07/2025"), None);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_752_582_896_120);
+        assert_eq!(format_timestamp(time), "2025-07-15T12:34:56.120Z");
+    }
+}